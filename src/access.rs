@@ -0,0 +1,67 @@
+//! Making sure the daemon has accessibility access before it starts.
+
+use crate::backend::WindowBackend;
+use crate::error::{ACCESSIBILITY_HELP, TileError};
+use std::thread;
+use std::time::Duration;
+
+/// Checks for accessibility access, explaining how to grant it and asking
+/// macOS to offer the setting when it is missing. Then, with `wait`, checks
+/// again every `poll` until access is granted; otherwise fails with
+/// [`TileError::NoAccess`].
+pub fn ensure_access(
+    backend: &mut dyn WindowBackend,
+    wait: bool,
+    poll: Duration,
+) -> Result<(), TileError> {
+    if backend.is_trusted(false) {
+        return Ok(());
+    }
+
+    eprintln!("✗ {}.", TileError::NoAccess);
+    eprintln!("{}", ACCESSIBILITY_HELP);
+    // Asking again makes macOS offer to open the setting
+    if backend.is_trusted(true) {
+        return Ok(());
+    }
+
+    if !wait {
+        return Err(TileError::NoAccess);
+    }
+    println!("Waiting for accessibility access...");
+    while !backend.is_trusted(false) {
+        thread::sleep(poll);
+    }
+    println!("✓ Accessibility access granted");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+
+    #[test]
+    fn granted_access_passes() {
+        let mut backend = MockBackend::new();
+        assert_eq!(ensure_access(&mut backend, false, Duration::ZERO), Ok(()));
+    }
+
+    #[test]
+    fn missing_access_fails_without_waiting() {
+        let mut backend = MockBackend::new().without_access();
+
+        let error = ensure_access(&mut backend, false, Duration::ZERO).unwrap_err();
+
+        assert_eq!(error, TileError::NoAccess);
+        assert!(error.is_permission_denied());
+    }
+
+    #[test]
+    fn waits_until_access_is_granted() {
+        let mut backend = MockBackend::new().without_access().granting_access_after(5);
+
+        assert_eq!(ensure_access(&mut backend, true, Duration::ZERO), Ok(()));
+        assert!(backend.is_trusted(false));
+    }
+}
@@ -0,0 +1,990 @@
+//! Tiling operations, written against [`WindowBackend`] so they run the same
+//! on real windows and on the in-memory mock.
+
+use crate::backend::{Display, WindowBackend, WindowId, WindowInfo};
+use crate::config::Rule;
+use crate::error::TileError;
+use crate::geometry::{Insets, Rect};
+use crate::history::{Change, History};
+use crate::layout::{self, BspTree, DwindleStyle, Gaps, LayoutKind, MasterStack, Snap, SplitAxis};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Everything a key binding can trigger, named in config files by the
+/// kebab-case variant name (`tile-left`, `auto-arrange`, ...).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    TileLeft,
+    TileRight,
+    TileTop,
+    TileBottom,
+    TileTopLeft,
+    TileTopRight,
+    TileBottomLeft,
+    TileBottomRight,
+    TileLeftThird,
+    TileCenterThird,
+    TileRightThird,
+    TileLeftTwoThirds,
+    TileRightTwoThirds,
+    Center,
+    Maximize,
+    AutoArrange,
+    ShrinkRatio,
+    GrowRatio,
+    DecreaseMasters,
+    IncreaseMasters,
+    ToggleBsp,
+    Rotate,
+    /// Mirrors the BSP tree left to right; also accepted as `flip`.
+    #[serde(alias = "flip")]
+    FlipHorizontal,
+    /// Mirrors the BSP tree top to bottom.
+    FlipVertical,
+    Balance,
+    Restore,
+    RestoreAll,
+    Undo,
+    Redo,
+    ReloadConfig,
+    /// Switches to the named binding mode; written `{ mode = "..." }`.
+    #[serde(skip)]
+    EnterMode(String),
+    /// Returns to the mode that was active before the current one.
+    ExitMode,
+    Quit,
+}
+
+impl Action {
+    /// Where the snap actions put the focused window.
+    pub fn snap(&self) -> Option<Snap> {
+        let snap = match self {
+            Action::TileLeft => Snap::LeftHalf,
+            Action::TileRight => Snap::RightHalf,
+            Action::TileTop => Snap::TopHalf,
+            Action::TileBottom => Snap::BottomHalf,
+            Action::TileTopLeft => Snap::TopLeft,
+            Action::TileTopRight => Snap::TopRight,
+            Action::TileBottomLeft => Snap::BottomLeft,
+            Action::TileBottomRight => Snap::BottomRight,
+            Action::TileLeftThird => Snap::LeftThird,
+            Action::TileCenterThird => Snap::CenterThird,
+            Action::TileRightThird => Snap::RightThird,
+            Action::TileLeftTwoThirds => Snap::LeftTwoThirds,
+            Action::TileRightTwoThirds => Snap::RightTwoThirds,
+            Action::Center => Snap::Center,
+            Action::Maximize => Snap::Maximize,
+            _ => return None,
+        };
+        Some(snap)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::TileLeft => "tile current window to left half",
+            Action::TileRight => "tile current window to right half",
+            Action::TileTop => "tile current window to top half",
+            Action::TileBottom => "tile current window to bottom half",
+            Action::TileTopLeft => "tile current window to top left quarter",
+            Action::TileTopRight => "tile current window to top right quarter",
+            Action::TileBottomLeft => "tile current window to bottom left quarter",
+            Action::TileBottomRight => "tile current window to bottom right quarter",
+            Action::TileLeftThird => "tile current window to left third",
+            Action::TileCenterThird => "tile current window to center third",
+            Action::TileRightThird => "tile current window to right third",
+            Action::TileLeftTwoThirds => "tile current window to left two thirds",
+            Action::TileRightTwoThirds => "tile current window to right two thirds",
+            Action::Center => "center current window",
+            Action::Maximize => "maximize current window",
+            Action::AutoArrange => "auto-arrange all visible windows",
+            Action::ShrinkRatio => "shrink the master area",
+            Action::GrowRatio => "grow the master area",
+            Action::DecreaseMasters => "remove a master window",
+            Action::IncreaseMasters => "add a master window",
+            Action::ToggleBsp => "toggle the BSP layout",
+            Action::Rotate => "rotate the BSP tree",
+            Action::FlipHorizontal => "flip the BSP tree left to right",
+            Action::FlipVertical => "flip the BSP tree top to bottom",
+            Action::Balance => "balance the BSP tree",
+            Action::Restore => "restore current window to where it was before tiling",
+            Action::RestoreAll => "restore all windows to where they were before tiling",
+            Action::Undo => "undo the last window arrangement",
+            Action::Redo => "redo the last undone window arrangement",
+            Action::ReloadConfig => "reload the config file",
+            Action::EnterMode(_) => "enter a binding mode",
+            Action::ExitMode => "leave the current binding mode",
+            Action::Quit => "quit",
+        }
+    }
+}
+
+/// Layout settings that persist between actions.
+#[derive(Debug, Clone, Default)]
+pub struct TilingState {
+    pub layout: LayoutKind,
+    /// Where toggling the BSP layout off returns to.
+    pub layout_before_bsp: LayoutKind,
+    pub master_stack: MasterStack,
+    pub dwindle_style: DwindleStyle,
+    pub gaps: Gaps,
+    /// Per-display replacements for the insets reported by the backend,
+    /// keyed by display id.
+    pub display_insets: HashMap<u32, Insets>,
+    /// Windows matching an unmanaged rule are never tiled.
+    pub rules: Vec<Rule>,
+    /// BSP trees keyed by display id.
+    pub bsp_trees: HashMap<u32, BspTree>,
+    /// The last snap applied to each window.
+    pub snaps: HashMap<WindowId, SnapRecord>,
+    /// Where each window was before the daemon first moved it.
+    pub original_frames: HashMap<WindowId, Rect>,
+    /// Recent arrangements, for undo and redo.
+    pub history: History,
+}
+
+/// What a snap action last did to a window, to recognise a repeated snap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapRecord {
+    pub snap: Snap,
+    /// Position in [`layout::CYCLE`].
+    pub step: usize,
+    /// The frame that was applied.
+    pub frame: Rect,
+}
+
+impl TilingState {
+    /// The area of `display` that layouts fill, honouring inset overrides.
+    pub fn work_area(&self, display: &Display) -> Rect {
+        match self.display_insets.get(&display.id) {
+            Some(&insets) => display.bounds.inset(insets),
+            None => display.work_area(),
+        }
+    }
+
+    pub fn is_managed(&self, window: &WindowInfo) -> bool {
+        !self
+            .rules
+            .iter()
+            .any(|rule| !rule.manage && rule.matches(window))
+    }
+}
+
+pub fn auto_arrange_windows(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    let mut windows = backend.visible_windows()?;
+    windows.retain(|window| state.is_managed(window));
+
+    if windows.is_empty() {
+        return Err(TileError::NoWindows);
+    }
+
+    println!("Found {} visible window(s) to arrange", windows.len());
+
+    let display = backend.main_display()?;
+    let screen = state.work_area(&display);
+    let ids: Vec<WindowId> = windows.iter().map(|w| w.id).collect();
+
+    match state.layout {
+        LayoutKind::Auto => {
+            let frames = state.gaps.apply(screen, |area| {
+                layout::auto(area, &ids, &state.master_stack, state.dwindle_style)
+            });
+            apply_frames(backend, state, &frames)?;
+
+            match windows.len() {
+                1 => println!("✓ Maximized single window"),
+                2 | 3 => println!(
+                    "✓ Arranged {} windows in master-stack layout",
+                    windows.len()
+                ),
+                4 => println!("✓ Arranged 4 windows in 2x2 grid"),
+                count => println!(
+                    "✓ Arranged {} windows in {:?} layout",
+                    count, state.dwindle_style
+                ),
+            }
+        }
+        LayoutKind::MasterStack => {
+            let frames = state
+                .gaps
+                .apply(screen, |area| state.master_stack.arrange(area, &ids));
+            apply_frames(backend, state, &frames)?;
+            println!(
+                "✓ Arranged {} windows in master-stack layout",
+                windows.len()
+            );
+        }
+        LayoutKind::Bsp => {
+            let displays = backend.displays()?;
+            let focused = backend.focused_window().ok().map(|w| w.id);
+            state
+                .bsp_trees
+                .retain(|id, _| displays.iter().any(|display| display.id == *id));
+
+            // Every display is synced, so a window that moved to another
+            // display leaves the tree it was in
+            let mut frames = Vec::new();
+            for display in &displays {
+                let ids: Vec<WindowId> = windows
+                    .iter()
+                    .filter(|w| display_of(&displays, &w.frame).map(|d| d.id) == Ok(display.id))
+                    .map(|w| w.id)
+                    .collect();
+                let screen = state.work_area(display);
+                let area = state.gaps.tiling_area(screen);
+                let tree = state.bsp_trees.entry(display.id).or_default();
+                tree.sync(&ids, focused, area);
+                frames.extend(state.gaps.apply(screen, |area| tree.arrange(area)));
+            }
+            apply_frames(backend, state, &frames)?;
+            println!("✓ Arranged {} windows in BSP layout", windows.len());
+        }
+    }
+
+    Ok(())
+}
+
+/// Switches between the BSP layout and the one used before it, and
+/// re-arranges.
+pub fn toggle_bsp_layout(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    state.layout = match state.layout {
+        LayoutKind::Bsp => state.layout_before_bsp,
+        layout => {
+            state.layout_before_bsp = layout;
+            LayoutKind::Bsp
+        }
+    };
+    println!("Layout: {:?}", state.layout);
+    auto_arrange_windows(backend, state)
+}
+
+/// Grows (positive `delta`) or shrinks the master area and re-arranges. In
+/// the BSP layout the split next to the focused window is moved instead.
+pub fn adjust_ratio(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+    delta: f64,
+) -> Result<(), TileError> {
+    match state.layout {
+        LayoutKind::Auto | LayoutKind::MasterStack => {
+            state.master_stack.adjust_ratio(delta);
+            println!("Master ratio: {:.2}", state.master_stack.ratio);
+        }
+        LayoutKind::Bsp => {
+            let focused = backend.focused_window()?;
+            let tree = focused_bsp_tree(backend, state)?;
+            if !tree.resize(focused.id, delta) {
+                return Err(TileError::NoSplit(focused.title));
+            }
+        }
+    }
+    auto_arrange_windows(backend, state)
+}
+
+/// Adds or removes windows from the master area and re-arranges.
+pub fn change_master_count(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+    delta: isize,
+) -> Result<(), TileError> {
+    state.master_stack.adjust_master_count(delta);
+    println!("Master count: {}", state.master_stack.master_count);
+    auto_arrange_windows(backend, state)
+}
+
+pub fn rotate_bsp(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    focused_bsp_tree(backend, state)?.rotate();
+    auto_arrange_windows(backend, state)
+}
+
+pub fn flip_bsp(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+    axis: SplitAxis,
+) -> Result<(), TileError> {
+    focused_bsp_tree(backend, state)?.flip(axis);
+    auto_arrange_windows(backend, state)
+}
+
+pub fn balance_bsp(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    focused_bsp_tree(backend, state)?.balance();
+    auto_arrange_windows(backend, state)
+}
+
+/// Apps may round a requested size, e.g. to whole terminal cells, so a
+/// window counts as still snapped when its edges are this close.
+const SNAP_TOLERANCE: f64 = 20.0;
+
+/// Moves the focused window to `snap` within the main display's work area,
+/// keeping the configured gaps. Snapping a window to the same half again
+/// while it is still there cycles its size through [`layout::CYCLE`].
+pub fn snap_focused_window(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+    snap: Snap,
+) -> Result<(), TileError> {
+    let window = backend.focused_window()?;
+
+    let screen = state.work_area(&backend.main_display()?);
+    let area = state.gaps.tiling_area(screen);
+
+    let current = backend.frame(window.id).ok();
+    let step = match state.snaps.get(&window.id) {
+        Some(last)
+            if snap.cycles()
+                && last.snap == snap
+                && current.is_some_and(|frame| frame.approx_eq(&last.frame, SNAP_TOLERANCE)) =>
+        {
+            (last.step + 1) % layout::CYCLE.len()
+        }
+        _ => 0,
+    };
+
+    let frame = state.gaps.separate(area, snap.cycle_frame(area, step));
+    apply_frames(backend, state, &[(window.id, frame)])?;
+    state
+        .snaps
+        .insert(window.id, SnapRecord { snap, step, frame });
+
+    if snap.cycles() {
+        println!(
+            "✓ Window '{}' tiled to {} ({:.0}%) successfully!",
+            window.title,
+            snap.description(),
+            layout::CYCLE[step] * 100.0
+        );
+    } else {
+        println!(
+            "✓ Window '{}' tiled to {} successfully!",
+            window.title,
+            snap.description()
+        );
+    }
+    Ok(())
+}
+
+/// Puts the focused window back where it was before it was first tiled.
+pub fn restore_focused_window(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    let window = backend.focused_window()?;
+    let frame = state
+        .original_frames
+        .remove(&window.id)
+        .ok_or_else(|| TileError::NotTiled(window.title.clone()))?;
+
+    backend.set_frame(window.id, frame)?;
+    state.snaps.remove(&window.id);
+    println!("✓ Window '{}' restored", window.title);
+    Ok(())
+}
+
+/// Puts every tiled window that is still open back where it was before it
+/// was first tiled, undoing auto-arrange and snaps alike.
+pub fn restore_all_windows(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+) -> Result<(), TileError> {
+    let open = open_windows(backend)?;
+    let mut frames: Vec<(WindowId, Rect)> = state
+        .original_frames
+        .drain()
+        .filter(|(window, _)| open.contains(window))
+        .collect();
+    frames.sort_by_key(|&(window, _)| window);
+    state.snaps.clear();
+
+    if frames.is_empty() {
+        return Err(TileError::NothingToRestore);
+    }
+    move_windows(backend, &frames)?;
+    println!("✓ Restored {} window(s)", frames.len());
+    Ok(())
+}
+
+/// Puts the windows of the last arrangement back where they were before it.
+/// Windows that have closed since are skipped.
+pub fn undo(backend: &mut dyn WindowBackend, state: &mut TilingState) -> Result<(), TileError> {
+    let open = open_windows(backend)?;
+    let frames = state.history.undo(&open).ok_or(TileError::NothingToUndo)?;
+    move_windows(backend, &frames)?;
+    println!("✓ Undid arrangement of {} window(s)", frames.len());
+    Ok(())
+}
+
+/// Reapplies the arrangement the last [`undo`] reverted.
+pub fn redo(backend: &mut dyn WindowBackend, state: &mut TilingState) -> Result<(), TileError> {
+    let open = open_windows(backend)?;
+    let frames = state.history.redo(&open).ok_or(TileError::NothingToRedo)?;
+    move_windows(backend, &frames)?;
+    println!("✓ Redid arrangement of {} window(s)", frames.len());
+    Ok(())
+}
+
+fn open_windows(backend: &mut dyn WindowBackend) -> Result<Vec<WindowId>, TileError> {
+    Ok(backend.visible_windows()?.iter().map(|w| w.id).collect())
+}
+
+/// The display a window at `frame` is on: the one its middle lies on, or
+/// the main display for a window that is on none of them.
+fn display_of<'a>(displays: &'a [Display], frame: &Rect) -> Result<&'a Display, TileError> {
+    displays
+        .iter()
+        .find(|display| display.contains(frame))
+        .or(displays.first())
+        .ok_or(TileError::NoDisplay)
+}
+
+/// The BSP tree of the focused window's display, or of the main display
+/// when no window is focused.
+fn focused_bsp_tree<'a>(
+    backend: &mut dyn WindowBackend,
+    state: &'a mut TilingState,
+) -> Result<&'a mut BspTree, TileError> {
+    if state.layout != LayoutKind::Bsp {
+        return Err(TileError::BspInactive);
+    }
+    let displays = backend.displays()?;
+    let display = match backend.focused_window() {
+        Ok(window) => display_of(&displays, &window.frame)?,
+        Err(_) => displays.first().ok_or(TileError::NoDisplay)?,
+    };
+    Ok(state.bsp_trees.entry(display.id).or_default())
+}
+
+/// Moves each window to its frame, remembering where windows that were not
+/// tiled before are and recording the arrangement for undo. Windows moved
+/// before a failure are still recorded.
+fn apply_frames(
+    backend: &mut dyn WindowBackend,
+    state: &mut TilingState,
+    frames: &[(WindowId, Rect)],
+) -> Result<(), TileError> {
+    let mut change = Change {
+        before: Vec::new(),
+        after: Vec::new(),
+    };
+    let mut result = Ok(());
+
+    for &(window, frame) in frames {
+        let current = backend.frame(window).ok();
+        if let Some(current) = current {
+            state.original_frames.entry(window).or_insert(current);
+        }
+        if let Err(e) = backend.set_frame(window, frame) {
+            result = Err(e);
+            break;
+        }
+        // Without the previous frame there is nothing to undo to
+        if let Some(current) = current {
+            change.before.push((window, current));
+            change.after.push((window, frame));
+        }
+    }
+
+    state.history.record(change);
+    result
+}
+
+/// Moves windows without recording anything, for undo, redo and restore.
+fn move_windows(
+    backend: &mut dyn WindowBackend,
+    frames: &[(WindowId, Rect)],
+) -> Result<(), TileError> {
+    for &(window, frame) in frames {
+        backend.set_frame(window, frame)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, Process};
+
+    const SCREEN: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1440.0,
+        height: 900.0,
+    };
+
+    /// A 1440x900 display with `count` windows, opened in order.
+    fn backend_with_windows(count: usize) -> (MockBackend, Vec<WindowId>) {
+        let mut backend = MockBackend::new().with_display(SCREEN);
+        let windows = (0..count)
+            .map(|i| {
+                let offset = 10.0 * i as f64;
+                backend.add_window(
+                    &format!("Window {}", i + 1),
+                    Rect::new(100.0 + offset, 100.0 + offset, 400.0, 300.0),
+                )
+            })
+            .collect();
+        (backend, windows)
+    }
+
+    fn assert_frames(actual: &[(WindowId, Rect)], expected: &[(WindowId, Rect)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for ((window, frame), (expected_window, expected_frame)) in actual.iter().zip(expected) {
+            assert_eq!(window, expected_window);
+            assert!(
+                frame.approx_eq(expected_frame, 1e-6),
+                "{:?}: {:?} != {:?}",
+                window,
+                frame,
+                expected_frame
+            );
+        }
+    }
+
+    fn arrange(count: usize) -> Vec<(WindowId, Rect)> {
+        let (mut backend, _) = backend_with_windows(count);
+        auto_arrange_windows(&mut backend, &mut TilingState::default()).unwrap();
+        backend.applied_frames().to_vec()
+    }
+
+    #[test]
+    fn auto_arrange_without_windows_fails() {
+        let (mut backend, _) = backend_with_windows(0);
+        let result = auto_arrange_windows(&mut backend, &mut TilingState::default());
+        assert_eq!(result, Err(TileError::NoWindows));
+        assert!(backend.applied_frames().is_empty());
+    }
+
+    #[test]
+    fn missing_access_is_not_mistaken_for_no_windows() {
+        let mut backend = MockBackend::new().with_display(SCREEN).without_access();
+        let mut state = TilingState::default();
+
+        let error = auto_arrange_windows(&mut backend, &mut state).unwrap_err();
+        assert!(error.is_permission_denied(), "{:?}", error);
+        assert!(!error.is_nothing_to_do());
+        assert!(
+            undo(&mut backend, &mut state)
+                .unwrap_err()
+                .is_permission_denied()
+        );
+        assert!(
+            restore_all_windows(&mut backend, &mut state)
+                .unwrap_err()
+                .is_permission_denied()
+        );
+    }
+
+    #[test]
+    fn auto_arrange_maximizes_one_window() {
+        let (_, w) = backend_with_windows(1);
+        assert_frames(&arrange(1), &[(w[0], SCREEN)]);
+    }
+
+    #[test]
+    fn auto_arrange_splits_two_windows() {
+        let (_, w) = backend_with_windows(2);
+        assert_frames(
+            &arrange(2),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 720.0, 900.0)),
+                (w[1], Rect::new(720.0, 0.0, 720.0, 900.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn auto_arrange_stacks_third_window() {
+        let (_, w) = backend_with_windows(3);
+        assert_frames(
+            &arrange(3),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 720.0, 900.0)),
+                (w[1], Rect::new(720.0, 0.0, 720.0, 450.0)),
+                (w[2], Rect::new(720.0, 450.0, 720.0, 450.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn auto_arrange_puts_four_windows_in_grid() {
+        let (_, w) = backend_with_windows(4);
+        assert_frames(
+            &arrange(4),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 720.0, 450.0)),
+                (w[1], Rect::new(720.0, 0.0, 720.0, 450.0)),
+                (w[2], Rect::new(0.0, 450.0, 720.0, 450.0)),
+                (w[3], Rect::new(720.0, 450.0, 720.0, 450.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn auto_arrange_dwindles_five_windows() {
+        let (_, w) = backend_with_windows(5);
+        assert_frames(
+            &arrange(5),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 720.0, 900.0)),
+                (w[1], Rect::new(720.0, 0.0, 720.0, 450.0)),
+                (w[2], Rect::new(720.0, 450.0, 360.0, 450.0)),
+                (w[3], Rect::new(1080.0, 450.0, 360.0, 225.0)),
+                (w[4], Rect::new(1080.0, 675.0, 360.0, 225.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn resizing_the_master_area_rearranges() {
+        let (mut backend, w) = backend_with_windows(3);
+        let mut state = TilingState::default();
+
+        adjust_ratio(&mut backend, &mut state, 0.25).unwrap();
+        change_master_count(&mut backend, &mut state, 1).unwrap();
+
+        assert_frames(
+            &backend.applied_frames()[3..],
+            &[
+                (w[0], Rect::new(0.0, 0.0, 1080.0, 450.0)),
+                (w[1], Rect::new(0.0, 450.0, 1080.0, 450.0)),
+                (w[2], Rect::new(1080.0, 0.0, 360.0, 900.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn master_stack_layout_applies_at_any_count() {
+        let (mut backend, w) = backend_with_windows(5);
+        let mut state = TilingState {
+            layout: LayoutKind::MasterStack,
+            ..TilingState::default()
+        };
+
+        adjust_ratio(&mut backend, &mut state, 0.25).unwrap();
+
+        assert_frames(
+            backend.applied_frames(),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 1080.0, 900.0)),
+                (w[1], Rect::new(1080.0, 0.0, 360.0, 225.0)),
+                (w[2], Rect::new(1080.0, 225.0, 360.0, 225.0)),
+                (w[3], Rect::new(1080.0, 450.0, 360.0, 225.0)),
+                (w[4], Rect::new(1080.0, 675.0, 360.0, 225.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn toggling_bsp_returns_to_the_previous_layout() {
+        let (mut backend, _) = backend_with_windows(2);
+        let mut state = TilingState {
+            layout: LayoutKind::MasterStack,
+            ..TilingState::default()
+        };
+
+        toggle_bsp_layout(&mut backend, &mut state).unwrap();
+        assert_eq!(state.layout, LayoutKind::Bsp);
+        toggle_bsp_layout(&mut backend, &mut state).unwrap();
+        assert_eq!(state.layout, LayoutKind::MasterStack);
+    }
+
+    #[test]
+    fn auto_arrange_applies_insets_and_gaps() {
+        let mut backend = MockBackend::new().with_inset_display(
+            SCREEN,
+            Insets {
+                top: 25.0,
+                ..Insets::default()
+            },
+        );
+        let first = backend.add_window("First", Rect::new(0.0, 0.0, 100.0, 100.0));
+        let second = backend.add_window("Second", Rect::new(0.0, 0.0, 100.0, 100.0));
+        let mut state = TilingState {
+            gaps: Gaps {
+                inner: 10.0,
+                outer: 20.0,
+                padding: Insets::default(),
+            },
+            ..TilingState::default()
+        };
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+
+        // Tiling area is (20, 45) to (1420, 880); the halves meet at 720
+        assert_frames(
+            backend.applied_frames(),
+            &[
+                (first, Rect::new(20.0, 45.0, 695.0, 835.0)),
+                (second, Rect::new(725.0, 45.0, 695.0, 835.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn unmanaged_apps_are_left_alone() {
+        let app = |pid, name: &str, bundle_id: &str| Process {
+            pid,
+            name: name.to_string(),
+            bundle_id: Some(bundle_id.to_string()),
+        };
+        let editor = app(10, "Editor", "com.example.editor");
+        let settings = app(20, "System Settings", "com.apple.systempreferences");
+        let mut backend = MockBackend::new().with_display(SCREEN);
+        let first = backend.add_app_window(&editor, "First", Rect::new(0.0, 0.0, 100.0, 100.0));
+        backend.add_app_window(&settings, "Settings", Rect::new(0.0, 0.0, 100.0, 100.0));
+        let second = backend.add_app_window(&editor, "Second", Rect::new(0.0, 0.0, 100.0, 100.0));
+        let mut state = TilingState {
+            rules: vec![Rule {
+                title: None,
+                app: None,
+                bundle_id: Some("com.apple.systempreferences".to_string()),
+                manage: false,
+            }],
+            ..TilingState::default()
+        };
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+
+        assert_frames(
+            backend.applied_frames(),
+            &[
+                (first, Rect::new(0.0, 0.0, 720.0, 900.0)),
+                (second, Rect::new(720.0, 0.0, 720.0, 900.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn auto_arrange_prefers_configured_insets() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState::default();
+        state.display_insets.insert(
+            1,
+            Insets {
+                bottom: 100.0,
+                ..Insets::default()
+            },
+        );
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+
+        assert_frames(
+            backend.applied_frames(),
+            &[(w[0], Rect::new(0.0, 0.0, 1440.0, 800.0))],
+        );
+    }
+
+    #[test]
+    fn bsp_layout_keeps_a_tree_per_display() {
+        let mut backend = MockBackend::new()
+            .with_display(SCREEN)
+            .with_display(Rect::new(1440.0, 0.0, 1280.0, 800.0));
+        let first = backend.add_window("First", Rect::new(100.0, 100.0, 400.0, 300.0));
+        let second = backend.add_window("Second", Rect::new(1600.0, 100.0, 400.0, 300.0));
+        let third = backend.add_window("Third", Rect::new(1700.0, 200.0, 400.0, 300.0));
+        let mut state = TilingState {
+            layout: LayoutKind::Bsp,
+            ..TilingState::default()
+        };
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+
+        assert_frames(
+            backend.applied_frames(),
+            &[
+                (first, SCREEN),
+                (second, Rect::new(1440.0, 0.0, 640.0, 800.0)),
+                (third, Rect::new(2080.0, 0.0, 640.0, 800.0)),
+            ],
+        );
+        assert_eq!(state.bsp_trees[&1].windows(), vec![first]);
+        assert_eq!(state.bsp_trees[&2].windows(), vec![second, third]);
+
+        // Moving a window to the other display moves it to that tree
+        backend
+            .set_frame(first, Rect::new(2000.0, 300.0, 400.0, 300.0))
+            .unwrap();
+        backend.focus(third);
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+
+        assert!(state.bsp_trees[&1].is_empty());
+        assert_eq!(state.bsp_trees[&2].windows(), vec![second, third, first]);
+    }
+
+    #[test]
+    fn bsp_actions_change_the_focused_windows_display() {
+        let mut backend = MockBackend::new()
+            .with_display(SCREEN)
+            .with_display(Rect::new(1440.0, 0.0, 1280.0, 800.0));
+        backend.add_window("First", Rect::new(100.0, 100.0, 400.0, 300.0));
+        backend.add_window("Second", Rect::new(200.0, 100.0, 400.0, 300.0));
+        let third = backend.add_window("Third", Rect::new(1600.0, 100.0, 400.0, 300.0));
+        let fourth = backend.add_window("Fourth", Rect::new(1700.0, 100.0, 400.0, 300.0));
+        let mut state = TilingState {
+            layout: LayoutKind::Bsp,
+            ..TilingState::default()
+        };
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+        let main_tree = state.bsp_trees[&1].clone();
+
+        backend.focus(third);
+        adjust_ratio(&mut backend, &mut state, 0.25).unwrap();
+
+        assert_eq!(state.bsp_trees[&1], main_tree);
+        assert_frames(
+            &backend.applied_frames()[backend.applied_frames().len() - 2..],
+            &[
+                (third, Rect::new(1440.0, 0.0, 960.0, 800.0)),
+                (fourth, Rect::new(2400.0, 0.0, 320.0, 800.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn snapping_a_half_again_cycles_its_size() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState::default();
+
+        for _ in 0..4 {
+            snap_focused_window(&mut backend, &mut state, Snap::LeftHalf).unwrap();
+        }
+
+        assert_frames(
+            backend.applied_frames(),
+            &[
+                (w[0], Rect::new(0.0, 0.0, 720.0, 900.0)),
+                (w[0], Rect::new(0.0, 0.0, 960.0, 900.0)),
+                (w[0], Rect::new(0.0, 0.0, 480.0, 900.0)),
+                (w[0], Rect::new(0.0, 0.0, 720.0, 900.0)),
+            ],
+        );
+    }
+
+    #[test]
+    fn snapping_a_moved_window_starts_the_cycle_over() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState::default();
+
+        snap_focused_window(&mut backend, &mut state, Snap::RightHalf).unwrap();
+        backend
+            .set_frame(w[0], Rect::new(300.0, 200.0, 500.0, 400.0))
+            .unwrap();
+        snap_focused_window(&mut backend, &mut state, Snap::RightHalf).unwrap();
+
+        assert_frames(
+            &backend.applied_frames()[2..],
+            &[(w[0], Rect::new(720.0, 0.0, 720.0, 900.0))],
+        );
+    }
+
+    #[test]
+    fn snap_keeps_gaps() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState {
+            gaps: Gaps {
+                inner: 10.0,
+                outer: 20.0,
+                padding: Insets::default(),
+            },
+            ..TilingState::default()
+        };
+
+        snap_focused_window(&mut backend, &mut state, Snap::TopRight).unwrap();
+
+        assert_frames(
+            backend.applied_frames(),
+            &[(w[0], Rect::new(725.0, 20.0, 695.0, 425.0))],
+        );
+    }
+
+    #[test]
+    fn restore_returns_window_to_its_original_frame() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState::default();
+        let original = backend.frame(w[0]).unwrap();
+
+        snap_focused_window(&mut backend, &mut state, Snap::LeftHalf).unwrap();
+        snap_focused_window(&mut backend, &mut state, Snap::Maximize).unwrap();
+        restore_focused_window(&mut backend, &mut state).unwrap();
+
+        assert_eq!(backend.applied_frames().last(), Some(&(w[0], original)));
+        assert_eq!(
+            restore_focused_window(&mut backend, &mut state),
+            Err(TileError::NotTiled("Window 1".to_string()))
+        );
+    }
+
+    #[test]
+    fn restore_all_returns_every_open_window() {
+        let (mut backend, w) = backend_with_windows(3);
+        let mut state = TilingState::default();
+        let originals: Vec<_> = w
+            .iter()
+            .map(|&id| (id, backend.frame(id).unwrap()))
+            .collect();
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+        let arranged = backend.applied_frames().len();
+        restore_all_windows(&mut backend, &mut state).unwrap();
+
+        assert_frames(&backend.applied_frames()[arranged..], &originals);
+        assert_eq!(
+            restore_all_windows(&mut backend, &mut state),
+            Err(TileError::NothingToRestore)
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_replay_the_last_arrangement() {
+        let (mut backend, w) = backend_with_windows(2);
+        let mut state = TilingState::default();
+        let originals: Vec<_> = w
+            .iter()
+            .map(|&id| (id, backend.frame(id).unwrap()))
+            .collect();
+
+        auto_arrange_windows(&mut backend, &mut state).unwrap();
+        let arranged = backend.applied_frames().to_vec();
+        undo(&mut backend, &mut state).unwrap();
+        redo(&mut backend, &mut state).unwrap();
+
+        assert_frames(&backend.applied_frames()[2..4], &originals);
+        assert_frames(&backend.applied_frames()[4..], &arranged);
+        assert_eq!(
+            redo(&mut backend, &mut state),
+            Err(TileError::NothingToRedo)
+        );
+    }
+
+    #[test]
+    fn undo_steps_back_through_snaps() {
+        let (mut backend, w) = backend_with_windows(1);
+        let mut state = TilingState::default();
+        let original = backend.frame(w[0]).unwrap();
+
+        snap_focused_window(&mut backend, &mut state, Snap::LeftHalf).unwrap();
+        snap_focused_window(&mut backend, &mut state, Snap::BottomHalf).unwrap();
+        undo(&mut backend, &mut state).unwrap();
+        undo(&mut backend, &mut state).unwrap();
+
+        assert_frames(
+            &backend.applied_frames()[2..],
+            &[(w[0], Rect::new(0.0, 0.0, 720.0, 900.0)), (w[0], original)],
+        );
+        assert_eq!(
+            undo(&mut backend, &mut state),
+            Err(TileError::NothingToUndo)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Typed access to accessibility attributes.
+//!
+//! The Accessibility API hands back untyped Core Foundation objects. The
+//! macOS backend decodes them into [`AttributeValue`]s, and everything past
+//! that point, such as deciding which windows to tile, only sees
+//! [`Attributes`], which a plain map can stand in for.
+
+use crate::geometry::Rect;
+use std::collections::HashMap;
+
+pub const ROLE: &str = "AXRole";
+pub const SUBROLE: &str = "AXSubrole";
+pub const TITLE: &str = "AXTitle";
+pub const POSITION: &str = "AXPosition";
+pub const SIZE: &str = "AXSize";
+pub const MINIMIZED: &str = "AXMinimized";
+pub const FULL_SCREEN: &str = "AXFullScreen";
+/// Set on an app element while the app is hidden with Cmd+H.
+pub const HIDDEN: &str = "AXHidden";
+
+// These hold other elements, which only the macOS backend can read
+pub const FOCUSED_APPLICATION: &str = "AXFocusedApplication";
+pub const FOCUSED_WINDOW: &str = "AXFocusedWindow";
+pub const WINDOWS: &str = "AXWindows";
+
+pub const STANDARD_WINDOW_SUBROLE: &str = "AXStandardWindow";
+
+/// An attribute value, decoded from CFBoolean, CFNumber, CFString or AXValue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Point { x: f64, y: f64 },
+    Size { width: f64, height: f64 },
+}
+
+/// Something whose accessibility attributes can be read, such as a window
+/// or app element.
+///
+/// The typed getters return `None` both when the attribute is missing and
+/// when it holds a value of another type.
+pub trait Attributes {
+    fn attribute(&self, name: &str) -> Option<AttributeValue>;
+
+    /// Reads a flag. Some apps report flags as the numbers 0 and 1.
+    fn bool(&self, name: &str) -> Option<bool> {
+        match self.attribute(name)? {
+            AttributeValue::Bool(value) => Some(value),
+            AttributeValue::Number(value) => Some(value != 0.0),
+            _ => None,
+        }
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        match self.attribute(name)? {
+            AttributeValue::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<String> {
+        match self.attribute(name)? {
+            AttributeValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The frame from the position and size attributes.
+    fn frame(&self) -> Option<Rect> {
+        let AttributeValue::Point { x, y } = self.attribute(POSITION)? else {
+            return None;
+        };
+        let AttributeValue::Size { width, height } = self.attribute(SIZE)? else {
+            return None;
+        };
+        Some(Rect::new(x, y, width, height))
+    }
+}
+
+/// A fixed set of attributes, keyed by name.
+impl Attributes for HashMap<String, AttributeValue> {
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        self.get(name).cloned()
+    }
+}
+
+/// Whether `window`, owned by `app`, should be tiled: a standard window that
+/// is neither minimized nor full screen, of an app that is not hidden.
+/// Dialogs, sheets and palettes have other subroles and are left alone.
+pub fn is_tileable(window: &impl Attributes, app: &impl Attributes) -> bool {
+    window.string(SUBROLE).as_deref() == Some(STANDARD_WINDOW_SUBROLE)
+        && !window.bool(MINIMIZED).unwrap_or(false)
+        && !window.bool(FULL_SCREEN).unwrap_or(false)
+        && !app.bool(HIDDEN).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes(values: &[(&str, AttributeValue)]) -> HashMap<String, AttributeValue> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect()
+    }
+
+    fn standard_window(extra: &[(&str, AttributeValue)]) -> HashMap<String, AttributeValue> {
+        let mut window = attributes(&[(
+            SUBROLE,
+            AttributeValue::String(STANDARD_WINDOW_SUBROLE.to_string()),
+        )]);
+        window.extend(attributes(extra));
+        window
+    }
+
+    fn app() -> HashMap<String, AttributeValue> {
+        HashMap::new()
+    }
+
+    #[test]
+    fn keeps_standard_windows() {
+        assert!(is_tileable(&standard_window(&[]), &app()));
+        assert!(is_tileable(
+            &standard_window(&[(MINIMIZED, AttributeValue::Bool(false))]),
+            &app()
+        ));
+    }
+
+    #[test]
+    fn drops_minimized_and_full_screen_windows() {
+        assert!(!is_tileable(
+            &standard_window(&[(MINIMIZED, AttributeValue::Bool(true))]),
+            &app()
+        ));
+        assert!(!is_tileable(
+            &standard_window(&[(FULL_SCREEN, AttributeValue::Bool(true))]),
+            &app()
+        ));
+    }
+
+    #[test]
+    fn drops_windows_of_hidden_apps() {
+        let hidden = attributes(&[(HIDDEN, AttributeValue::Bool(true))]);
+        assert!(!is_tileable(&standard_window(&[]), &hidden));
+    }
+
+    #[test]
+    fn drops_other_subroles() {
+        let dialog = attributes(&[(SUBROLE, AttributeValue::String("AXDialog".to_string()))]);
+        assert!(!is_tileable(&dialog, &app()));
+        assert!(!is_tileable(&app(), &app()));
+    }
+
+    #[test]
+    fn numbers_read_as_flags() {
+        let window = attributes(&[
+            (MINIMIZED, AttributeValue::Number(1.0)),
+            (FULL_SCREEN, AttributeValue::Number(0.0)),
+            (TITLE, AttributeValue::String("Notes".to_string())),
+        ]);
+
+        assert_eq!(window.bool(MINIMIZED), Some(true));
+        assert_eq!(window.bool(FULL_SCREEN), Some(false));
+        assert_eq!(window.bool(TITLE), None);
+        assert_eq!(window.bool(HIDDEN), None);
+        assert_eq!(window.number(MINIMIZED), Some(1.0));
+        assert_eq!(window.string(MINIMIZED), None);
+        assert!(!is_tileable(
+            &standard_window(&[(MINIMIZED, AttributeValue::Number(1.0))]),
+            &app()
+        ));
+    }
+
+    #[test]
+    fn frame_needs_a_point_and_a_size() {
+        let position = (POSITION, AttributeValue::Point { x: 10.0, y: 20.0 });
+        let size = (
+            SIZE,
+            AttributeValue::Size {
+                width: 300.0,
+                height: 200.0,
+            },
+        );
+
+        assert_eq!(
+            attributes(&[position.clone(), size]).frame(),
+            Some(Rect::new(10.0, 20.0, 300.0, 200.0))
+        );
+        assert_eq!(attributes(std::slice::from_ref(&position)).frame(), None);
+        assert_eq!(
+            attributes(&[position, (SIZE, AttributeValue::Point { x: 1.0, y: 2.0 })]).frame(),
+            None
+        );
+    }
+}
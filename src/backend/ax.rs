@@ -0,0 +1,569 @@
+use super::attributes::{self, AttributeValue, Attributes, is_tileable};
+use super::retained::{RefCounted, Retained};
+use super::{Display, Process, ProcessList, WindowBackend, WindowId, WindowInfo};
+use crate::error::{AxError, TileError};
+use crate::geometry::{Insets, Rect};
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFRelease, CFRetain, TCFType};
+use core_foundation::base::{CFType, CFTypeID, CFTypeRef};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use core_graphics::display::CGDisplay;
+use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+use core_graphics::window::{
+    create_window_list, kCGNullWindowID, kCGWindowListExcludeDesktopElements,
+    kCGWindowListOptionOnScreenOnly,
+};
+use objc::rc::autoreleasepool;
+use objc::runtime::Object;
+use objc::{class, msg_send, sel, sel_impl};
+use std::collections::{HashMap, HashSet};
+
+// FFI bindings to Accessibility API
+type AXUIElementRef = *const std::ffi::c_void;
+type AXError = i32;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+unsafe extern "C" {
+    static kAXTrustedCheckOptionPrompt: CFStringRef;
+    fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+    fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+    fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+    fn AXUIElementGetPid(element: AXUIElementRef, pid: *mut i32) -> AXError;
+    fn AXUIElementGetTypeID() -> CFTypeID;
+    fn AXUIElementCopyAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: *mut CFTypeRef,
+    ) -> AXError;
+    fn AXUIElementSetAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: CFTypeRef,
+    ) -> AXError;
+    fn AXValueCreate(value_type: u32, value_ptr: *const std::ffi::c_void) -> CFTypeRef;
+    fn AXValueGetTypeID() -> CFTypeID;
+    fn AXValueGetType(value: CFTypeRef) -> u32;
+    fn AXValueGetValue(value: CFTypeRef, value_type: u32, value_ptr: *mut std::ffi::c_void)
+    -> bool;
+    // Private, but stable for years and used by every major tiling WM to
+    // map an AX element to its CGWindowID.
+    fn _AXUIElementGetWindow(element: AXUIElementRef, window_id: *mut u32) -> AXError;
+}
+
+// NSScreen and NSWorkspace live in AppKit; nothing is called directly, the
+// link just makes sure the classes are registered with the Objective-C runtime
+#[link(name = "AppKit", kind = "framework")]
+unsafe extern "C" {}
+
+// AXValueType tags
+const K_AX_VALUE_CG_POINT_TYPE: u32 = 1;
+const K_AX_VALUE_CG_SIZE_TYPE: u32 = 2;
+
+// NSApplicationActivationPolicyRegular: an ordinary app with a Dock icon
+const NS_APPLICATION_ACTIVATION_POLICY_REGULAR: isize = 0;
+
+/// The running apps as NSWorkspace reports them.
+#[derive(Debug, Default)]
+pub struct Workspace;
+
+impl Workspace {
+    /// The running app with `pid`, if there is one.
+    pub fn process(pid: i32) -> Option<Process> {
+        autoreleasepool(|| unsafe {
+            let app: *mut Object = msg_send![
+                class!(NSRunningApplication),
+                runningApplicationWithProcessIdentifier: pid
+            ];
+            (!app.is_null()).then(|| describe_app(app))
+        })
+    }
+}
+
+impl ProcessList for Workspace {
+    fn processes(&mut self) -> Result<Vec<Process>, TileError> {
+        // The daemon's threads have no autorelease pool of their own
+        autoreleasepool(|| unsafe {
+            let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let apps: *mut Object = msg_send![workspace, runningApplications];
+            if apps.is_null() {
+                return Err(TileError::System(
+                    "Failed to list running applications".to_string(),
+                ));
+            }
+
+            let mut processes = Vec::new();
+            let count: usize = msg_send![apps, count];
+            for i in 0..count {
+                let app: *mut Object = msg_send![apps, objectAtIndex: i];
+                let policy: isize = msg_send![app, activationPolicy];
+                if policy == NS_APPLICATION_ACTIVATION_POLICY_REGULAR {
+                    processes.push(describe_app(app));
+                }
+            }
+            Ok(processes)
+        })
+    }
+}
+
+/// Reads the pid, name and bundle id of an NSRunningApplication.
+///
+/// # Safety
+///
+/// `app` must point to an NSRunningApplication, and an autorelease pool must
+/// be in place.
+unsafe fn describe_app(app: *mut Object) -> Process {
+    unsafe {
+        let pid: i32 = msg_send![app, processIdentifier];
+        let name: *mut Object = msg_send![app, localizedName];
+        let bundle_id: *mut Object = msg_send![app, bundleIdentifier];
+        Process {
+            pid,
+            name: ns_string(name).unwrap_or_else(|| "Unknown".to_string()),
+            bundle_id: ns_string(bundle_id),
+        }
+    }
+}
+
+/// Backend driving real windows through the macOS Accessibility API.
+///
+/// Windows are enumerated across the apps listed by [`Workspace`].
+#[derive(Debug, Default)]
+pub struct AxBackend {
+    processes: Workspace,
+    /// Elements of the windows seen so far that may still be on screen.
+    elements: HashMap<WindowId, AxElement>,
+}
+
+impl AxBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn element(&self, window: WindowId) -> Result<&AxElement, TileError> {
+        self.elements
+            .get(&window)
+            .ok_or(TileError::UnknownWindow(window))
+    }
+
+    /// The tileable windows of `app` that are in `on_screen`; see
+    /// [`is_tileable`].
+    fn windows_of(
+        &mut self,
+        app: &Process,
+        on_screen: &HashSet<u32>,
+    ) -> Result<Vec<WindowInfo>, AxError> {
+        let app_element = AxElement::application(app.pid).ok_or(AxError::InvalidElement)?;
+
+        let mut window_infos = Vec::new();
+        for window in app_element.windows()? {
+            if !is_tileable(&window, &app_element) {
+                continue;
+            }
+            if let Ok(info) = self.window_info(window, app)
+                && on_screen.contains(&info.id.0)
+            {
+                window_infos.push(info);
+            }
+        }
+        Ok(window_infos)
+    }
+
+    /// Describes `window`, which belongs to `app`, and remembers its element.
+    fn window_info(&mut self, window: AxElement, app: &Process) -> Result<WindowInfo, TileError> {
+        let id = window
+            .window_id()
+            .map_err(|error| TileError::ax(error, "window id", None))?;
+        let info = WindowInfo {
+            id,
+            title: window
+                .string(attributes::TITLE)
+                .unwrap_or_else(|| "Unknown".to_string()),
+            pid: app.pid,
+            app_name: app.name.clone(),
+            bundle_id: app.bundle_id.clone(),
+            role: window.string(attributes::ROLE),
+            subrole: window.string(attributes::SUBROLE),
+            frame: window.checked_frame(id)?,
+        };
+        self.elements.insert(id, window);
+        Ok(info)
+    }
+}
+
+impl WindowBackend for AxBackend {
+    fn displays(&mut self) -> Result<Vec<Display>, TileError> {
+        let main = CGDisplay::main();
+        let mut ids = CGDisplay::active_displays()
+            .map_err(|e| TileError::System(format!("Failed to list displays (CGError {})", e)))?;
+        // Main display first, the rest in the order the system reports them
+        ids.sort_by_key(|&id| id != main.id);
+
+        let mut insets = screen_insets();
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let bounds = CGDisplay::new(id).bounds();
+                Display {
+                    id,
+                    bounds: Rect::new(
+                        bounds.origin.x,
+                        bounds.origin.y,
+                        bounds.size.width,
+                        bounds.size.height,
+                    ),
+                    insets: insets.remove(&id).unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+
+    fn visible_windows(&mut self) -> Result<Vec<WindowInfo>, TileError> {
+        // Windows on other spaces and of hidden apps are not on screen
+        let on_screen: HashSet<u32> = create_window_list(
+            kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+            kCGNullWindowID,
+        )
+        .ok_or_else(|| TileError::System("Failed to list on-screen windows".to_string()))?
+        .iter()
+        .map(|id| *id)
+        .collect();
+
+        // Let go of windows that have closed or left the screen
+        self.elements.retain(|id, _| on_screen.contains(&id.0));
+
+        let mut all_windows = Vec::new();
+        for process in self.processes.processes()? {
+            match self.windows_of(&process, &on_screen) {
+                Ok(windows) => all_windows.extend(windows),
+                // The app quit since it was listed, or has no windows
+                Err(AxError::InvalidElement | AxError::NoValue | AxError::AttributeUnsupported) => {
+                }
+                // Such as missing access, which no other app will get past
+                Err(error) => return Err(TileError::ax(error, attributes::WINDOWS, None)),
+            }
+        }
+        Ok(all_windows)
+    }
+
+    fn focused_window(&mut self) -> Result<WindowInfo, TileError> {
+        // No value means nothing has focus, e.g. the desktop is active
+        let focus_error = |attribute| {
+            move |error| match error {
+                AxError::NoValue => TileError::NoFocusedWindow,
+                error => TileError::ax(error, attribute, None),
+            }
+        };
+
+        let focused_app = AxElement::system_wide()
+            .ok_or_else(|| TileError::System("Failed to create system-wide element".to_string()))?
+            .element(attributes::FOCUSED_APPLICATION)
+            .map_err(focus_error(attributes::FOCUSED_APPLICATION))?;
+        let focused_window = focused_app
+            .element(attributes::FOCUSED_WINDOW)
+            .map_err(focus_error(attributes::FOCUSED_WINDOW))?;
+
+        let pid = focused_window.pid().unwrap_or(0);
+        let app = Workspace::process(pid).unwrap_or_else(|| Process {
+            pid,
+            name: "Unknown".to_string(),
+            bundle_id: None,
+        });
+        self.window_info(focused_window, &app)
+    }
+
+    fn frame(&mut self, window: WindowId) -> Result<Rect, TileError> {
+        self.element(window)?.checked_frame(window)
+    }
+
+    fn set_frame(&mut self, window: WindowId, frame: Rect) -> Result<(), TileError> {
+        let element = self.element(window)?;
+        let position = ax_point(CGPoint::new(frame.x, frame.y))?;
+        let size = ax_size(CGSize::new(frame.width, frame.height))?;
+        element
+            .set(attributes::POSITION, &position)
+            .and_then(|()| element.set(attributes::SIZE, &size))
+            .map_err(|(error, attribute)| TileError::ax(error, attribute, Some(window)))
+    }
+
+    fn is_trusted(&mut self, prompt: bool) -> bool {
+        unsafe {
+            let options = CFDictionary::from_CFType_pairs(&[(
+                CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt),
+                CFBoolean::from(prompt),
+            )]);
+            AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
+        }
+    }
+}
+
+/// Space taken by the menu bar and Dock on each display, keyed by display id.
+///
+/// CoreGraphics only knows the full display bounds, so this compares each
+/// NSScreen's frame with its visible frame.
+fn screen_insets() -> HashMap<u32, Insets> {
+    let mut insets = HashMap::new();
+
+    autoreleasepool(|| unsafe {
+        let screens: *mut Object = msg_send![class!(NSScreen), screens];
+        if screens.is_null() {
+            return;
+        }
+
+        // NSString and CFString are toll-free bridged
+        let number_key = CFString::from_static_string("NSScreenNumber");
+        let number_key = number_key.as_concrete_TypeRef() as *mut Object;
+
+        let count: usize = msg_send![screens, count];
+        for i in 0..count {
+            let screen: *mut Object = msg_send![screens, objectAtIndex: i];
+            let description: *mut Object = msg_send![screen, deviceDescription];
+            let number: *mut Object = msg_send![description, objectForKey: number_key];
+            if number.is_null() {
+                continue;
+            }
+            let id: u32 = msg_send![number, unsignedIntValue];
+
+            let frame: CGRect = msg_send![screen, frame];
+            let visible: CGRect = msg_send![screen, visibleFrame];
+
+            // NSScreen has a bottom-left origin, so the top inset is measured
+            // between the maximum y values
+            insets.insert(
+                id,
+                Insets {
+                    top: (frame.origin.y + frame.size.height)
+                        - (visible.origin.y + visible.size.height),
+                    right: (frame.origin.x + frame.size.width)
+                        - (visible.origin.x + visible.size.width),
+                    bottom: visible.origin.y - frame.origin.y,
+                    left: visible.origin.x - frame.origin.x,
+                },
+            );
+        }
+    });
+
+    insets
+}
+
+/// Copies an NSString, which may be nil.
+///
+/// # Safety
+///
+/// `string` must be nil or point to an NSString.
+unsafe fn ns_string(string: *mut Object) -> Option<String> {
+    // NSString and CFString are toll-free bridged
+    (!string.is_null())
+        .then(|| unsafe { CFString::wrap_under_get_rule(string as CFStringRef) }.to_string())
+}
+
+/// Core Foundation objects, which AX elements are.
+#[derive(Debug)]
+enum CoreFoundation {}
+
+impl RefCounted for CoreFoundation {
+    unsafe fn retain(handle: *const std::ffi::c_void) {
+        unsafe { CFRetain(handle) };
+    }
+
+    unsafe fn release(handle: *const std::ffi::c_void) {
+        unsafe { CFRelease(handle) };
+    }
+}
+
+/// An owned reference to an AX element, released when dropped.
+#[derive(Debug, Clone, PartialEq)]
+struct AxElement(Retained<CoreFoundation>);
+
+// Retaining and releasing CF objects is thread-safe, and AX elements may be
+// used from any thread.
+unsafe impl Send for AxElement {}
+
+impl AxElement {
+    fn system_wide() -> Option<Self> {
+        unsafe { Retained::from_create(AXUIElementCreateSystemWide()).map(AxElement) }
+    }
+
+    fn application(pid: i32) -> Option<Self> {
+        unsafe { Retained::from_create(AXUIElementCreateApplication(pid)).map(AxElement) }
+    }
+
+    fn as_ptr(&self) -> AXUIElementRef {
+        self.0.as_ptr()
+    }
+
+    /// An attribute holding another element, such as the focused window.
+    fn element(&self, name: &str) -> Result<AxElement, AxError> {
+        let value = self.copy(name)?;
+        if value.type_of() != unsafe { AXUIElementGetTypeID() } {
+            return Err(AxError::IllegalArgument);
+        }
+        unsafe { Retained::from_get(value.as_CFTypeRef()) }
+            .map(AxElement)
+            .ok_or(AxError::NoValue)
+    }
+
+    /// The windows of an app element.
+    fn windows(&self) -> Result<Vec<AxElement>, AxError> {
+        let windows = self.copy(attributes::WINDOWS)?;
+        unsafe {
+            let windows = windows
+                .downcast::<CFArray>()
+                .ok_or(AxError::IllegalArgument)?;
+            // The array holds its own references, so each item is retained
+            Ok(windows
+                .iter()
+                .filter_map(|window| Retained::from_get(*window).map(AxElement))
+                .collect())
+        }
+    }
+
+    /// The CGWindowID of a window element.
+    fn window_id(&self) -> Result<WindowId, AxError> {
+        let mut id: u32 = 0;
+        match AxError::from_code(unsafe { _AXUIElementGetWindow(self.as_ptr(), &mut id) }) {
+            Some(error) => Err(error),
+            None => Ok(WindowId(id)),
+        }
+    }
+
+    /// Like [`Attributes::frame`], but says why the frame could not be read.
+    fn checked_frame(&self, window: WindowId) -> Result<Rect, TileError> {
+        self.frame().ok_or_else(|| {
+            let (attribute, error) = [attributes::POSITION, attributes::SIZE]
+                .into_iter()
+                .find_map(|attribute| self.copy(attribute).err().map(|error| (attribute, error)))
+                // Readable, but not a point or a size
+                .unwrap_or((attributes::POSITION, AxError::IllegalArgument));
+            TileError::ax(error, attribute, Some(window))
+        })
+    }
+
+    fn pid(&self) -> Option<i32> {
+        let mut pid = 0;
+        (unsafe { AXUIElementGetPid(self.as_ptr(), &mut pid) } == 0).then_some(pid)
+    }
+
+    fn copy(&self, name: &str) -> Result<CFType, AxError> {
+        let attribute = CFString::new(name);
+        let mut value: CFTypeRef = std::ptr::null();
+        let result = unsafe {
+            AXUIElementCopyAttributeValue(
+                self.as_ptr(),
+                attribute.as_concrete_TypeRef(),
+                &mut value,
+            )
+        };
+        if let Some(error) = AxError::from_code(result) {
+            return Err(error);
+        }
+        if value.is_null() {
+            return Err(AxError::NoValue);
+        }
+        Ok(unsafe { CFType::wrap_under_create_rule(value) })
+    }
+
+    /// Sets an attribute to `value`. Failures come with the attribute's name.
+    fn set(&self, name: &'static str, value: &CFType) -> Result<(), (AxError, &'static str)> {
+        let attribute = CFString::new(name);
+        let result = unsafe {
+            AXUIElementSetAttributeValue(
+                self.as_ptr(),
+                attribute.as_concrete_TypeRef(),
+                value.as_CFTypeRef(),
+            )
+        };
+        match AxError::from_code(result) {
+            Some(error) => Err((error, name)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Attributes for AxElement {
+    fn attribute(&self, name: &str) -> Option<AttributeValue> {
+        decode(self.copy(name).ok()?)
+    }
+}
+
+/// Converts a CFBoolean, CFNumber, CFString or a point or size AXValue.
+/// Other types, such as elements and arrays, give `None`.
+fn decode(value: CFType) -> Option<AttributeValue> {
+    if let Some(flag) = value.downcast::<CFBoolean>() {
+        return Some(AttributeValue::Bool(flag.into()));
+    }
+    if let Some(number) = value.downcast::<CFNumber>() {
+        return number.to_f64().map(AttributeValue::Number);
+    }
+    if let Some(string) = value.downcast::<CFString>() {
+        return Some(AttributeValue::String(string.to_string()));
+    }
+
+    unsafe {
+        if value.type_of() != AXValueGetTypeID() {
+            return None;
+        }
+        let value = value.as_CFTypeRef();
+        match AXValueGetType(value) {
+            K_AX_VALUE_CG_POINT_TYPE => {
+                let mut point = CGPoint::new(0.0, 0.0);
+                AXValueGetValue(
+                    value,
+                    K_AX_VALUE_CG_POINT_TYPE,
+                    &mut point as *mut _ as *mut _,
+                )
+                .then_some(AttributeValue::Point {
+                    x: point.x,
+                    y: point.y,
+                })
+            }
+            K_AX_VALUE_CG_SIZE_TYPE => {
+                let mut size = CGSize::new(0.0, 0.0);
+                AXValueGetValue(
+                    value,
+                    K_AX_VALUE_CG_SIZE_TYPE,
+                    &mut size as *mut _ as *mut _,
+                )
+                .then_some(AttributeValue::Size {
+                    width: size.width,
+                    height: size.height,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Wraps `point` in an AXValue, the form the position attribute is set in.
+fn ax_point(point: CGPoint) -> Result<CFType, TileError> {
+    // The tag matches what the pointer points to
+    unsafe {
+        ax_value(
+            K_AX_VALUE_CG_POINT_TYPE,
+            &point as *const CGPoint as *const _,
+        )
+    }
+}
+
+/// Wraps `size` in an AXValue, the form the size attribute is set in.
+fn ax_size(size: CGSize) -> Result<CFType, TileError> {
+    // The tag matches what the pointer points to
+    unsafe { ax_value(K_AX_VALUE_CG_SIZE_TYPE, &size as *const CGSize as *const _) }
+}
+
+/// # Safety
+///
+/// `value` must point to a valid value of the type `value_type` tags, such
+/// as a `CGPoint` for `K_AX_VALUE_CG_POINT_TYPE`; AXValueCreate copies that
+/// many bytes from it.
+unsafe fn ax_value(value_type: u32, value: *const std::ffi::c_void) -> Result<CFType, TileError> {
+    unsafe {
+        let value = AXValueCreate(value_type, value);
+        if value.is_null() {
+            return Err(TileError::System("Failed to create AXValue".to_string()));
+        }
+        Ok(CFType::wrap_under_create_rule(value))
+    }
+}
@@ -0,0 +1,280 @@
+use super::attributes::{self, AttributeValue, is_tileable};
+use super::{Display, Process, ProcessList, WindowBackend, WindowId, WindowInfo};
+use crate::error::{AxError, TileError};
+use crate::geometry::{Insets, Rect};
+use std::collections::HashMap;
+
+/// In-memory backend with virtual displays, apps, windows and focus.
+///
+/// Like the macOS backend, windows are listed app by app, in the order the
+/// apps were first seen, and only those [`is_tileable`] judges worth tiling.
+///
+/// Every successful `set_frame` call is recorded in order so callers can
+/// assert the exact frames an operation applied.
+#[derive(Debug, Default)]
+pub struct MockBackend {
+    displays: Vec<Display>,
+    processes: Vec<Process>,
+    windows: Vec<WindowInfo>,
+    /// Flags such as minimized, by window and by app pid.
+    window_flags: HashMap<WindowId, HashMap<String, AttributeValue>>,
+    app_flags: HashMap<i32, HashMap<String, AttributeValue>>,
+    focused: Option<WindowId>,
+    applied: Vec<(WindowId, Rect)>,
+    next_id: u32,
+    untrusted: bool,
+    /// Access checks left until access is granted, if it ever is.
+    checks_until_granted: Option<usize>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_display(self, bounds: Rect) -> Self {
+        self.with_inset_display(bounds, Insets::default())
+    }
+
+    /// Adds a display whose edges are partly reserved, e.g. by a menu bar.
+    pub fn with_inset_display(mut self, bounds: Rect, insets: Insets) -> Self {
+        let id = self.displays.len() as u32 + 1;
+        self.displays.push(Display { id, bounds, insets });
+        self
+    }
+
+    /// Behaves as if accessibility access had not been granted: windows can
+    /// neither be found nor moved until [`MockBackend::grant_access`].
+    pub fn without_access(mut self) -> Self {
+        self.untrusted = true;
+        self
+    }
+
+    /// Grants access once `is_trusted` has said no `checks` times, like a
+    /// user turning it on while the daemon waits.
+    pub fn granting_access_after(mut self, checks: usize) -> Self {
+        self.checks_until_granted = Some(checks);
+        self
+    }
+
+    pub fn grant_access(&mut self) {
+        self.untrusted = false;
+        self.checks_until_granted = None;
+    }
+
+    /// Opens a window of an anonymous app and focuses it.
+    pub fn add_window(&mut self, title: &str, frame: Rect) -> WindowId {
+        let app = Process {
+            pid: 0,
+            name: "Mock".to_string(),
+            bundle_id: None,
+        };
+        self.add_app_window(&app, title, frame)
+    }
+
+    /// Opens a standard window of `app` and focuses it, like a newly
+    /// launched app would.
+    pub fn add_app_window(&mut self, app: &Process, title: &str, frame: Rect) -> WindowId {
+        self.open(app, title, attributes::STANDARD_WINDOW_SUBROLE, frame)
+    }
+
+    /// Opens a dialog of `app` and focuses it.
+    pub fn add_dialog(&mut self, app: &Process, title: &str, frame: Rect) -> WindowId {
+        self.open(app, title, "AXDialog", frame)
+    }
+
+    /// Sets a flag such as [`attributes::MINIMIZED`] on `window`.
+    pub fn set_window_flag(&mut self, window: WindowId, name: &str, value: bool) {
+        self.window_flags
+            .entry(window)
+            .or_default()
+            .insert(name.to_string(), AttributeValue::Bool(value));
+    }
+
+    /// Sets a flag such as [`attributes::HIDDEN`] on the app with `pid`.
+    pub fn set_app_flag(&mut self, pid: i32, name: &str, value: bool) {
+        self.app_flags
+            .entry(pid)
+            .or_default()
+            .insert(name.to_string(), AttributeValue::Bool(value));
+    }
+
+    pub fn focus(&mut self, window: WindowId) {
+        self.focused = Some(window);
+    }
+
+    pub fn applied_frames(&self) -> &[(WindowId, Rect)] {
+        &self.applied
+    }
+
+    fn open(&mut self, app: &Process, title: &str, subrole: &str, frame: Rect) -> WindowId {
+        if !self.processes.iter().any(|process| process.pid == app.pid) {
+            self.processes.push(app.clone());
+        }
+        self.next_id += 1;
+        let id = WindowId(self.next_id);
+        self.windows.push(WindowInfo {
+            id,
+            title: title.to_string(),
+            pid: app.pid,
+            app_name: app.name.clone(),
+            bundle_id: app.bundle_id.clone(),
+            role: Some("AXWindow".to_string()),
+            subrole: Some(subrole.to_string()),
+            frame,
+        });
+        self.focused = Some(id);
+        id
+    }
+
+    /// The attributes the macOS backend would read from `window`.
+    fn window_attributes(&self, window: &WindowInfo) -> HashMap<String, AttributeValue> {
+        let mut values = self
+            .window_flags
+            .get(&window.id)
+            .cloned()
+            .unwrap_or_default();
+        for (name, value) in [
+            (attributes::ROLE, &window.role),
+            (attributes::SUBROLE, &window.subrole),
+        ] {
+            if let Some(value) = value {
+                values.insert(name.to_string(), AttributeValue::String(value.clone()));
+            }
+        }
+        values
+    }
+
+    fn window(&self, window: WindowId) -> Option<&WindowInfo> {
+        self.windows.iter().find(|w| w.id == window)
+    }
+
+    /// Fails the way the Accessibility API does without access.
+    fn check_access(&self, attribute: &'static str) -> Result<(), TileError> {
+        match self.untrusted {
+            true => Err(TileError::ax(AxError::ApiDisabled, attribute, None)),
+            false => Ok(()),
+        }
+    }
+}
+
+impl WindowBackend for MockBackend {
+    fn displays(&mut self) -> Result<Vec<Display>, TileError> {
+        Ok(self.displays.clone())
+    }
+
+    fn visible_windows(&mut self) -> Result<Vec<WindowInfo>, TileError> {
+        self.check_access(attributes::WINDOWS)?;
+        let no_flags = HashMap::new();
+        let mut visible = Vec::new();
+        for process in self.processes.processes()? {
+            let app = self.app_flags.get(&process.pid).unwrap_or(&no_flags);
+            visible.extend(
+                self.windows
+                    .iter()
+                    .filter(|w| {
+                        w.pid == process.pid && is_tileable(&self.window_attributes(w), app)
+                    })
+                    .cloned(),
+            );
+        }
+        Ok(visible)
+    }
+
+    fn focused_window(&mut self) -> Result<WindowInfo, TileError> {
+        self.check_access(attributes::FOCUSED_WINDOW)?;
+        self.focused
+            .and_then(|id| self.window(id))
+            .cloned()
+            .ok_or(TileError::NoFocusedWindow)
+    }
+
+    fn frame(&mut self, window: WindowId) -> Result<Rect, TileError> {
+        self.window(window)
+            .map(|w| w.frame)
+            .ok_or(TileError::UnknownWindow(window))
+    }
+
+    fn set_frame(&mut self, window: WindowId, frame: Rect) -> Result<(), TileError> {
+        self.check_access(attributes::POSITION)?;
+        let target = self
+            .windows
+            .iter_mut()
+            .find(|w| w.id == window)
+            .ok_or(TileError::UnknownWindow(window))?;
+        target.frame = frame;
+        self.applied.push((window, frame));
+        Ok(())
+    }
+
+    fn is_trusted(&mut self, _prompt: bool) -> bool {
+        match self.checks_until_granted {
+            Some(0) => self.grant_access(),
+            Some(checks) => self.checks_until_granted = Some(checks - 1),
+            None => {}
+        }
+        !self.untrusted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(pid: i32, name: &str) -> Process {
+        Process {
+            pid,
+            name: name.to_string(),
+            bundle_id: Some(format!("com.example.{}", name.to_lowercase())),
+        }
+    }
+
+    fn titles(backend: &mut MockBackend) -> Vec<String> {
+        backend
+            .visible_windows()
+            .unwrap()
+            .into_iter()
+            .map(|w| w.title)
+            .collect()
+    }
+
+    const FRAME: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 400.0,
+        height: 300.0,
+    };
+
+    #[test]
+    fn windows_are_listed_app_by_app() {
+        let (editor, browser) = (app(10, "Editor"), app(20, "Browser"));
+        let mut backend = MockBackend::new();
+        backend.add_app_window(&editor, "Notes", FRAME);
+        let docs = backend.add_app_window(&browser, "Docs", FRAME);
+        backend.add_app_window(&editor, "Todo", FRAME);
+
+        assert_eq!(titles(&mut backend), ["Notes", "Todo", "Docs"]);
+        let window = backend.window(docs).unwrap();
+        assert_eq!(window.pid, 20);
+        assert_eq!(window.bundle_id.as_deref(), Some("com.example.browser"));
+    }
+
+    #[test]
+    fn only_tileable_windows_are_listed() {
+        let (editor, browser) = (app(10, "Editor"), app(20, "Browser"));
+        let mut backend = MockBackend::new();
+        backend.add_app_window(&editor, "Notes", FRAME);
+        backend.add_dialog(&editor, "Save", FRAME);
+        let todo = backend.add_app_window(&editor, "Todo", FRAME);
+        let video = backend.add_app_window(&editor, "Video", FRAME);
+        backend.add_app_window(&browser, "Docs", FRAME);
+
+        backend.set_window_flag(todo, attributes::MINIMIZED, true);
+        backend.set_window_flag(video, attributes::FULL_SCREEN, true);
+        assert_eq!(titles(&mut backend), ["Notes", "Docs"]);
+
+        backend.set_app_flag(20, attributes::HIDDEN, true);
+        backend.set_window_flag(todo, attributes::MINIMIZED, false);
+        assert_eq!(titles(&mut backend), ["Notes", "Todo"]);
+    }
+}
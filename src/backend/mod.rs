@@ -0,0 +1,118 @@
+//! Window system backends.
+//!
+//! All window enumeration, focus queries and frame changes go through the
+//! [`WindowBackend`] trait so the tiling logic never talks to the
+//! Accessibility API directly. On macOS the daemon uses [`AxBackend`]; the
+//! in-memory [`MockBackend`] stands in for it on other platforms and in tests.
+
+pub mod attributes;
+#[cfg(target_os = "macos")]
+mod ax;
+mod mock;
+pub mod retained;
+
+#[cfg(target_os = "macos")]
+pub use ax::{AxBackend, Workspace};
+pub use mock::MockBackend;
+
+use crate::error::TileError;
+use crate::geometry::{Insets, Rect};
+
+/// Stable identifier of a window for as long as it stays open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WindowId(pub u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub id: WindowId,
+    pub title: String,
+    /// Process id of the app owning the window.
+    pub pid: i32,
+    /// The owning app's name as shown in the Dock.
+    pub app_name: String,
+    /// Such as `com.apple.Safari`. Apps without a bundle have none.
+    pub bundle_id: Option<String>,
+    /// Accessibility role, `AXWindow` for windows.
+    pub role: Option<String>,
+    /// Accessibility subrole: `AXStandardWindow`, `AXDialog`, ...
+    pub subrole: Option<String>,
+    /// Where the window was when it was queried.
+    pub frame: Rect,
+}
+
+/// A running app that may own windows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub pid: i32,
+    pub name: String,
+    pub bundle_id: Option<String>,
+}
+
+/// Lists the apps whose windows are candidates for tiling.
+///
+/// Kept apart from the window queries so that which apps get enumerated can
+/// be swapped out; a plain `Vec<Process>` serves as a fixed list, as in
+/// [`MockBackend`].
+pub trait ProcessList {
+    /// Regular apps, the kind that appear in the Dock. Agents and background
+    /// daemons are left out.
+    fn processes(&mut self) -> Result<Vec<Process>, TileError>;
+}
+
+impl ProcessList for Vec<Process> {
+    fn processes(&mut self) -> Result<Vec<Process>, TileError> {
+        Ok(self.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    pub id: u32,
+    /// The full display, including the menu bar and Dock.
+    pub bounds: Rect,
+    /// Space along each edge that windows should not cover.
+    pub insets: Insets,
+}
+
+impl Display {
+    /// The part of the display windows can use.
+    pub fn work_area(&self) -> Rect {
+        self.bounds.inset(self.insets)
+    }
+
+    /// Whether the middle of `frame` lies on this display.
+    pub fn contains(&self, frame: &Rect) -> bool {
+        let (x, y) = (frame.x + frame.width / 2.0, frame.y + frame.height / 2.0);
+        (self.bounds.x..self.bounds.right()).contains(&x)
+            && (self.bounds.y..self.bounds.bottom()).contains(&y)
+    }
+}
+
+pub trait WindowBackend {
+    /// All active displays, with the main display first.
+    fn displays(&mut self) -> Result<Vec<Display>, TileError>;
+
+    /// Windows that are candidates for tiling, in arrangement order.
+    fn visible_windows(&mut self) -> Result<Vec<WindowInfo>, TileError>;
+
+    fn focused_window(&mut self) -> Result<WindowInfo, TileError>;
+
+    /// Where `window` currently is, in the same coordinates as `set_frame`.
+    fn frame(&mut self, window: WindowId) -> Result<Rect, TileError>;
+
+    fn set_frame(&mut self, window: WindowId, frame: Rect) -> Result<(), TileError>;
+
+    /// Whether the daemon may control other apps' windows. With `prompt`,
+    /// the system is also asked to offer granting access if it is missing.
+    fn is_trusted(&mut self, prompt: bool) -> bool {
+        let _ = prompt;
+        true
+    }
+
+    fn main_display(&mut self) -> Result<Display, TileError> {
+        self.displays()?
+            .into_iter()
+            .next()
+            .ok_or(TileError::NoDisplay)
+    }
+}
@@ -0,0 +1,159 @@
+//! Ownership of reference-counted handles from C APIs.
+
+use std::ffi::c_void;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// How one kind of handle is retained and released, e.g. `CFRetain` and
+/// `CFRelease` for Core Foundation objects.
+pub trait RefCounted {
+    /// # Safety
+    ///
+    /// `handle` must be a live handle of this kind.
+    unsafe fn retain(handle: *const c_void);
+
+    /// # Safety
+    ///
+    /// `handle` must be a live handle of this kind that the caller owns a
+    /// reference to, which is given up.
+    unsafe fn release(handle: *const c_void);
+}
+
+/// Owns one reference to a handle: cloning retains it again and dropping
+/// releases it.
+pub struct Retained<R: RefCounted> {
+    handle: NonNull<c_void>,
+    kind: PhantomData<R>,
+}
+
+impl<R: RefCounted> Retained<R> {
+    /// Takes over a reference the caller already owns, such as the result of
+    /// a Create or Copy function. Null gives `None`.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be null or a live handle of kind `R` whose reference is
+    /// not released anywhere else.
+    pub unsafe fn from_create(handle: *const c_void) -> Option<Self> {
+        NonNull::new(handle as *mut c_void).map(|handle| Retained {
+            handle,
+            kind: PhantomData,
+        })
+    }
+
+    /// Retains a handle owned by someone else, such as an item of an array.
+    /// Null gives `None`.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be null or a live handle of kind `R`.
+    pub unsafe fn from_get(handle: *const c_void) -> Option<Self> {
+        unsafe {
+            let retained = Self::from_create(handle)?;
+            R::retain(handle);
+            Some(retained)
+        }
+    }
+
+    /// The handle, valid for as long as `self` is alive.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.handle.as_ptr()
+    }
+}
+
+impl<R: RefCounted> Clone for Retained<R> {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_get(self.as_ptr()) }.expect("handle is not null")
+    }
+}
+
+impl<R: RefCounted> Drop for Retained<R> {
+    fn drop(&mut self) {
+        unsafe { R::release(self.as_ptr()) }
+    }
+}
+
+impl<R: RefCounted> PartialEq for Retained<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl<R: RefCounted> fmt::Debug for Retained<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Retained({:p})", self.handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    thread_local! {
+        static RETAINS: Cell<usize> = const { Cell::new(0) };
+        static RELEASES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts calls instead of touching the handle. Tests run on their own
+    /// threads, so the counts are per test.
+    enum Counting {}
+
+    impl RefCounted for Counting {
+        unsafe fn retain(_handle: *const c_void) {
+            RETAINS.set(RETAINS.get() + 1);
+        }
+
+        unsafe fn release(_handle: *const c_void) {
+            RELEASES.set(RELEASES.get() + 1);
+        }
+    }
+
+    /// (retains, releases) so far.
+    fn counts() -> (usize, usize) {
+        (RETAINS.get(), RELEASES.get())
+    }
+
+    fn handle() -> *const c_void {
+        NonNull::<u8>::dangling().as_ptr() as *const c_void
+    }
+
+    #[test]
+    fn from_create_releases_once_on_drop() {
+        let retained = unsafe { Retained::<Counting>::from_create(handle()) }.unwrap();
+        assert_eq!(counts(), (0, 0));
+
+        drop(retained);
+        assert_eq!(counts(), (0, 1));
+    }
+
+    #[test]
+    fn from_get_retains() {
+        let retained = unsafe { Retained::<Counting>::from_get(handle()) }.unwrap();
+        assert_eq!(counts(), (1, 0));
+
+        drop(retained);
+        assert_eq!(counts(), (1, 1));
+    }
+
+    #[test]
+    fn clone_retains_and_both_release() {
+        let retained = unsafe { Retained::<Counting>::from_create(handle()) }.unwrap();
+        let clone = retained.clone();
+        assert_eq!(counts(), (1, 0));
+        assert_eq!(clone, retained);
+        assert_eq!(clone.as_ptr(), handle());
+
+        drop(retained);
+        drop(clone);
+        assert_eq!(counts(), (1, 2));
+    }
+
+    #[test]
+    fn null_is_not_retained() {
+        assert!(unsafe { Retained::<Counting>::from_create(std::ptr::null()) }.is_none());
+        assert!(unsafe { Retained::<Counting>::from_get(std::ptr::null()) }.is_none());
+        assert_eq!(counts(), (0, 0));
+    }
+}
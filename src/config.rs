@@ -0,0 +1,822 @@
+//! User configuration, read from `~/.config/osx-tiles/config.toml`.
+//!
+//! Every table is optional and anything left out keeps its built-in default,
+//! so an empty file behaves exactly like no file at all. The schema is
+//! documented in the README.
+
+use crate::actions::{Action, TilingState};
+use crate::backend::WindowInfo;
+use crate::geometry::Insets;
+use crate::hotkeys::{self, Binding, Dispatcher, Keymap, Mode};
+use crate::layout::{self, DwindleStyle, Gaps, LayoutKind, MasterStack, StackSide};
+use serde::Deserialize;
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, Deserializer, IntoDeserializer, MapAccess, Visitor};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use toml::Spanned;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    pub layout: LayoutKind,
+    pub master_stack: MasterStack,
+    pub dwindle_style: DwindleStyle,
+    pub gaps: Gaps,
+    /// Insets that replace what the backend reports, keyed by display id.
+    pub display_insets: HashMap<u32, Insets>,
+    pub keymap: Keymap,
+    /// Keep hotkeys from reaching the focused app. Only read at startup,
+    /// since it decides how keyboard events are tapped.
+    pub grab_hotkeys: bool,
+    /// Wait at startup until accessibility access is granted instead of
+    /// exiting without it.
+    pub wait_for_permission: bool,
+    pub rules: Vec<Rule>,
+}
+
+/// Selects windows by their properties. Every matcher that is set must
+/// match; a rule needs at least one.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Matches windows whose title contains this text.
+    pub title: Option<String>,
+    /// Matches windows of the app with exactly this name, e.g. `Finder`.
+    pub app: Option<String>,
+    /// Matches windows of the app with this bundle id, e.g.
+    /// `com.apple.finder`.
+    #[serde(rename = "bundle-id")]
+    pub bundle_id: Option<String>,
+    /// Whether matching windows are tiled at all.
+    pub manage: bool,
+}
+
+impl Rule {
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        self.title
+            .as_deref()
+            .is_none_or(|title| window.title.contains(title))
+            && self.app.as_ref().is_none_or(|app| *app == window.app_name)
+            && self
+                .bundle_id
+                .as_ref()
+                .is_none_or(|id| window.bundle_id.as_ref() == Some(id))
+    }
+
+    fn has_matcher(&self) -> bool {
+        self.title.is_some() || self.app.is_some() || self.bundle_id.is_some()
+    }
+}
+
+// The file format. Kept separate from `Config` so values can be validated
+// with their position in the file before they are accepted.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    /// Seconds.
+    #[serde(rename = "sequence-timeout")]
+    sequence_timeout: Option<Spanned<f64>>,
+    #[serde(rename = "grab-hotkeys")]
+    grab_hotkeys: bool,
+    #[serde(rename = "wait-for-permission")]
+    wait_for_permission: bool,
+    layout: RawLayout,
+    gaps: Gaps,
+    displays: BTreeMap<Spanned<String>, RawDisplay>,
+    bindings: Option<RawBindings>,
+    modes: BTreeMap<Spanned<String>, RawMode>,
+    rules: Vec<Spanned<Rule>>,
+}
+
+type RawBindings = BTreeMap<Spanned<String>, RawBinding>;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMode {
+    /// Seconds.
+    timeout: Option<Spanned<f64>>,
+    bindings: RawBindings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct RawLayout {
+    default: LayoutKind,
+    master_ratio: Option<Spanned<f64>>,
+    master_count: Option<usize>,
+    stack_side: Option<StackSide>,
+    dwindle_style: DwindleStyle,
+}
+
+/// Either just an action name, `{ action = "...", repeat = true }` or
+/// `{ mode = "..." }`.
+#[derive(Debug)]
+struct RawBinding {
+    action: Action,
+    repeat: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBindingTable {
+    action: Option<Action>,
+    mode: Option<String>,
+    #[serde(default)]
+    repeat: bool,
+}
+
+impl<'de> Deserialize<'de> for RawBinding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RawBindingVisitor;
+
+        impl<'de> Visitor<'de> for RawBindingVisitor {
+            type Value = RawBinding;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an action name or a table with `action` or `mode`")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<RawBinding, E> {
+                Ok(RawBinding {
+                    action: Action::deserialize(name.into_deserializer())?,
+                    repeat: false,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<RawBinding, A::Error> {
+                let table = RawBindingTable::deserialize(MapAccessDeserializer::new(map))?;
+                let action = match (table.action, table.mode) {
+                    (Some(action), None) => action,
+                    (None, Some(mode)) => Action::EnterMode(mode),
+                    _ => {
+                        return Err(de::Error::custom(
+                            "binding needs exactly one of `action` and `mode`",
+                        ));
+                    }
+                };
+                Ok(RawBinding {
+                    action,
+                    repeat: table.repeat,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(RawBindingVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDisplay {
+    insets: Insets,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".config/osx-tiles/config.toml"))
+    }
+
+    /// Reads the config at `path`. A missing file yields the defaults.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match std::fs::read_to_string(path) {
+            Ok(source) => Config::parse(&source, path),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// Parses and validates `source`. Errors are reported as
+    /// `path:line:column: message`.
+    pub fn parse(source: &str, path: &Path) -> Result<Config, String> {
+        let error_at = |offset: usize, message: &dyn Display| {
+            let (line, column) = line_column(source, offset);
+            format!("{}:{}:{}: {}", path.display(), line, column, message)
+        };
+
+        let raw: RawConfig = toml::from_str(source).map_err(|e| {
+            let offset = e.span().map_or(0, |span| span.start);
+            error_at(offset, &e.message())
+        })?;
+
+        let mut config = Config {
+            layout: raw.layout.default,
+            dwindle_style: raw.layout.dwindle_style,
+            gaps: raw.gaps,
+            grab_hotkeys: raw.grab_hotkeys,
+            wait_for_permission: raw.wait_for_permission,
+            ..Config::default()
+        };
+
+        if let Some(ratio) = raw.layout.master_ratio {
+            if !layout::RATIO_RANGE.contains(ratio.get_ref()) {
+                return Err(error_at(
+                    ratio.span().start,
+                    &format!(
+                        "master-ratio must be between {} and {}",
+                        layout::RATIO_RANGE.start(),
+                        layout::RATIO_RANGE.end()
+                    ),
+                ));
+            }
+            config.master_stack.ratio = *ratio.get_ref();
+        }
+        if let Some(count) = raw.layout.master_count {
+            config.master_stack.master_count = count;
+        }
+        if let Some(side) = raw.layout.stack_side {
+            config.master_stack.stack_side = side;
+        }
+
+        for (id, display) in raw.displays {
+            let display_id = id.get_ref().parse::<u32>().map_err(|_| {
+                error_at(
+                    id.span().start,
+                    &format!("display id `{}` is not a number", id.get_ref()),
+                )
+            })?;
+            config.display_insets.insert(display_id, display.insets);
+        }
+
+        let mode_names: Vec<String> = raw
+            .modes
+            .keys()
+            .map(|name| name.get_ref().clone())
+            .collect();
+        let mut bindings = hotkeys::default_bindings();
+        if let Some(raw_bindings) = raw.bindings {
+            bindings = parse_bindings(raw_bindings, &mode_names, &error_at)?;
+        }
+
+        let mut modes = HashMap::new();
+        for (name, raw_mode) in raw.modes {
+            let timeout = raw_mode
+                .timeout
+                .map(|seconds| parse_seconds(seconds, "mode timeout", &error_at))
+                .transpose()?;
+
+            let mut bindings = parse_bindings(raw_mode.bindings, &mode_names, &error_at)?;
+            // Escape always gets you out unless the mode binds it itself
+            let escape = Binding::parse("escape", Action::ExitMode).expect("escape binding");
+            if !bindings
+                .iter()
+                .any(|b| b.sequence.conflicts(&escape.sequence))
+            {
+                bindings.push(escape);
+            }
+
+            modes.insert(name.into_inner(), Mode { bindings, timeout });
+        }
+
+        let sequence_timeout = raw
+            .sequence_timeout
+            .map(|seconds| parse_seconds(seconds, "sequence-timeout", &error_at))
+            .transpose()?
+            .unwrap_or(hotkeys::DEFAULT_SEQUENCE_TIMEOUT);
+        config.keymap = Keymap::new(bindings, modes, sequence_timeout);
+
+        for rule in raw.rules {
+            if !rule.get_ref().has_matcher() {
+                return Err(error_at(
+                    rule.span().start,
+                    &"rule needs at least one matcher such as `title`",
+                ));
+            }
+            config.rules.push(rule.into_inner());
+        }
+
+        Ok(config)
+    }
+
+    /// Copies the layout settings into `state`, leaving runtime state such as
+    /// BSP trees alone.
+    pub fn apply(&self, state: &mut TilingState) {
+        state.layout = self.layout;
+        state.master_stack = self.master_stack;
+        state.dwindle_style = self.dwindle_style;
+        state.gaps = self.gaps;
+        state.display_insets = self.display_insets.clone();
+        state.rules = self.rules.clone();
+    }
+
+    /// Loads the file at `path` and, if it is valid, applies it to `state`
+    /// and swaps in its keymap, returning `dispatcher` to the default
+    /// bindings. An invalid file leaves all three untouched.
+    pub fn reload(
+        path: &Path,
+        state: &mut TilingState,
+        keymap: &mut Keymap,
+        dispatcher: &mut Dispatcher,
+    ) -> Result<(), String> {
+        let config = Config::load(path)?;
+        config.apply(state);
+        *keymap = config.keymap;
+        dispatcher.reset_modes();
+        Ok(())
+    }
+}
+
+/// Parses one bindings table, in file order so a conflict is reported where
+/// the chord is repeated.
+fn parse_bindings(
+    raw_bindings: RawBindings,
+    mode_names: &[String],
+    error_at: &impl Fn(usize, &dyn Display) -> String,
+) -> Result<Vec<Binding>, String> {
+    let mut raw_bindings: Vec<_> = raw_bindings.into_iter().collect();
+    raw_bindings.sort_by_key(|(sequence, _)| sequence.span().start);
+
+    let bindings = raw_bindings
+        .iter()
+        .map(|(sequence, binding)| {
+            if let Action::EnterMode(mode) = &binding.action
+                && !mode_names.contains(mode)
+            {
+                return Err(error_at(
+                    sequence.span().start,
+                    &format!("unknown mode `{}`; define it as [modes.{}]", mode, mode),
+                ));
+            }
+            Binding::parse(sequence.get_ref(), binding.action.clone())
+                .map(|parsed| parsed.repeating(binding.repeat))
+                .map_err(|e| error_at(sequence.span().start, &e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some((earlier, later)) = hotkeys::find_conflict(&bindings) {
+        let (sequence, _) = &raw_bindings[later];
+        let (earlier_sequence, _) = &raw_bindings[earlier];
+        let (first, second) = (&bindings[earlier].sequence, &bindings[later].sequence);
+        let message = if first == second {
+            format!(
+                "`{}` is already bound by `{}` (both mean `{}`)",
+                sequence.get_ref(),
+                earlier_sequence.get_ref(),
+                second
+            )
+        } else if first.chords().len() != second.chords().len() {
+            format!(
+                "`{}` and `{}` start the same way; a binding cannot be the beginning of another",
+                sequence.get_ref(),
+                earlier_sequence.get_ref()
+            )
+        } else {
+            format!(
+                "`{}` overlaps `{}`; make the modifier sides distinct, e.g. `lctrl` and `rctrl`",
+                sequence.get_ref(),
+                earlier_sequence.get_ref()
+            )
+        };
+        return Err(error_at(sequence.span().start, &message));
+    }
+
+    Ok(bindings)
+}
+
+fn parse_seconds(
+    seconds: Spanned<f64>,
+    name: &str,
+    error_at: &impl Fn(usize, &dyn Display) -> String,
+) -> Result<Duration, String> {
+    let value = *seconds.get_ref();
+    if !(value > 0.0 && value.is_finite()) {
+        return Err(error_at(
+            seconds.span().start,
+            &format!("{} must be a positive number of seconds", name),
+        ));
+    }
+    Ok(Duration::from_secs_f64(value))
+}
+
+/// Notices when the config file is created, modified or deleted.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    /// Starts watching from the file's current state, so only later changes
+    /// are reported.
+    pub fn new(path: PathBuf) -> Self {
+        let last_modified = modified_time(&path);
+        ConfigWatcher {
+            path,
+            last_modified,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true once for every change since the previous call.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified == self.last_modified {
+            return false;
+        }
+        self.last_modified = modified;
+        true
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// 1-based line and column of a byte offset.
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Config, String> {
+        Config::parse(source, Path::new("config.toml"))
+    }
+
+    #[test]
+    fn empty_file_keeps_the_defaults() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn bindings_replace_the_defaults() {
+        let config = parse(
+            r#"
+[bindings]
+"Shift+Ctrl+T" = "tile-right"
+"ctrl+space, w, l" = "tile-left"
+"#,
+        )
+        .unwrap();
+
+        let bindings: Vec<(String, Action)> = config
+            .keymap
+            .bindings()
+            .iter()
+            .map(|b| (b.sequence.to_string(), b.action.clone()))
+            .collect();
+        assert_eq!(
+            bindings,
+            vec![
+                ("ctrl+shift+t".to_string(), Action::TileRight),
+                ("ctrl+space, w, l".to_string(), Action::TileLeft),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_binding_reports_where_it_is_repeated() {
+        let error = parse(
+            r#"
+[bindings]
+"ctrl+shift+t" = "tile-left"
+"Shift+Ctrl+T" = "tile-right"
+"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            "config.toml:4:1: `Shift+Ctrl+T` is already bound by `ctrl+shift+t` \
+             (both mean `ctrl+shift+t`)"
+        );
+    }
+
+    #[test]
+    fn overlapping_bindings_are_rejected() {
+        let error = parse(
+            r#"
+[bindings]
+"ctrl+t" = "tile-left"
+"rctrl+t" = "tile-right"
+"#,
+        )
+        .unwrap_err();
+        assert!(error.starts_with("config.toml:4:1: `rctrl+t` overlaps `ctrl+t`"));
+
+        let error = parse(
+            r#"
+[bindings]
+"ctrl+space" = "tile-left"
+"ctrl+space, w" = "tile-right"
+"#,
+        )
+        .unwrap_err();
+        assert!(error.starts_with("config.toml:4:1: `ctrl+space, w` and `ctrl+space` start"));
+    }
+
+    #[test]
+    fn invalid_chord_reports_its_position() {
+        let error = parse(
+            r#"
+[bindings]
+"ctrl+a" = "tile-left"
+  "ctrl+shift" = "tile-right"
+"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            "config.toml:4:3: `ctrl+shift` has no key besides modifiers, e.g. `ctrl+shift+t`"
+        );
+    }
+
+    fn mode_bindings(config: &Config, name: &str) -> Vec<(String, Action)> {
+        config
+            .keymap
+            .mode(name)
+            .unwrap()
+            .bindings
+            .iter()
+            .map(|b| (b.sequence.to_string(), b.action.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn modes_get_their_own_bindings_and_escape() {
+        let config = parse(
+            r#"
+[bindings]
+"ctrl+alt+r" = { mode = "resize" }
+
+[modes.resize]
+timeout = 5
+[modes.resize.bindings]
+"h" = { action = "shrink-ratio", repeat = true }
+"m" = { mode = "move" }
+
+[modes.move.bindings]
+"l" = "tile-right"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.keymap.bindings()[0].action,
+            Action::EnterMode("resize".to_string())
+        );
+        assert_eq!(
+            mode_bindings(&config, "resize"),
+            vec![
+                ("h".to_string(), Action::ShrinkRatio),
+                ("m".to_string(), Action::EnterMode("move".to_string())),
+                ("escape".to_string(), Action::ExitMode),
+            ]
+        );
+        assert!(config.keymap.mode("resize").unwrap().bindings[0].repeat);
+        assert_eq!(
+            config.keymap.mode("resize").unwrap().timeout,
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(config.keymap.mode("move").unwrap().timeout, None);
+        assert_eq!(
+            mode_bindings(&config, "move"),
+            vec![
+                ("l".to_string(), Action::TileRight),
+                ("escape".to_string(), Action::ExitMode),
+            ]
+        );
+    }
+
+    #[test]
+    fn mode_can_bind_escape_itself() {
+        let config = parse(
+            r#"
+[modes.resize.bindings]
+"esc" = "balance"
+"q" = "exit-mode"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            mode_bindings(&config, "resize"),
+            vec![
+                ("escape".to_string(), Action::Balance),
+                ("q".to_string(), Action::ExitMode),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_mode_is_an_error() {
+        let error = parse(
+            r#"
+[bindings]
+"ctrl+alt+r" = { mode = "resize" }
+"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "config.toml:3:1: unknown mode `resize`; define it as [modes.resize]"
+        );
+
+        let error = parse(
+            r#"
+[modes.resize.bindings]
+"h" = "shrink-ratio"
+"m" = { mode = "move" }
+"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "config.toml:4:1: unknown mode `move`; define it as [modes.move]"
+        );
+    }
+
+    #[test]
+    fn mode_bindings_are_checked_for_conflicts() {
+        let error = parse(
+            r#"
+[modes.resize.bindings]
+"h" = "shrink-ratio"
+"H" = "grow-ratio"
+"#,
+        )
+        .unwrap_err();
+
+        assert!(error.starts_with("config.toml:4:1: `H` is already bound by `h`"));
+    }
+
+    #[test]
+    fn layout_settings_are_read() {
+        let config = parse(
+            r#"
+[layout]
+default = "master-stack"
+master-ratio = 0.6
+master-count = 2
+stack-side = "bottom"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.layout, LayoutKind::MasterStack);
+        assert_eq!(config.master_stack.ratio, 0.6);
+        assert_eq!(config.master_stack.master_count, 2);
+        assert_eq!(config.master_stack.stack_side, StackSide::Bottom);
+    }
+
+    /// A path in the temp directory no other test uses.
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("osx-tiles-{}-{}.toml", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// Writes `contents` and moves the modification time on, so the change
+    /// shows even where timestamps are coarse.
+    fn write(path: &Path, contents: &str, seconds_later: u64) {
+        std::fs::write(path, contents).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(seconds_later))
+            .unwrap();
+    }
+
+    #[test]
+    fn master_ratio_must_be_in_range() {
+        let error = parse("[layout]\nmaster-ratio = 0.95\n").unwrap_err();
+
+        assert_eq!(
+            error,
+            "config.toml:2:16: master-ratio must be between 0.1 and 0.9"
+        );
+    }
+
+    #[test]
+    fn display_insets_are_keyed_by_id() {
+        let config = parse(
+            r#"
+[displays.42]
+insets = { top = 25, right = 0, bottom = 0, left = 0 }
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.display_insets[&42].top, 25.0);
+
+        let error = parse("[displays.main]\ninsets = {}\n").unwrap_err();
+        assert!(
+            error.contains("display id `main` is not a number"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rules_need_a_matcher() {
+        let error = parse("[[rules]]\nmanage = false\n").unwrap_err();
+
+        assert!(error.ends_with("rule needs at least one matcher such as `title`"));
+    }
+
+    #[test]
+    fn watcher_reports_each_change_once() {
+        let path = temp_path("watcher");
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(!watcher.changed());
+
+        write(&path, "grab-hotkeys = false", 10);
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        write(&path, "grab-hotkeys = true", 20);
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+    }
+
+    #[test]
+    fn watcher_ignores_the_state_it_started_in() {
+        let path = temp_path("existing");
+        write(&path, "grab-hotkeys = false", 10);
+
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(!watcher.changed());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn invalid_reload_keeps_the_running_config() {
+        let path = temp_path("reload");
+        let config = parse(
+            r#"
+[layout]
+default = "bsp"
+[gaps]
+inner = 8
+[bindings]
+"ctrl+alt+r" = { mode = "resize" }
+[modes.resize.bindings]
+"h" = "shrink-ratio"
+"#,
+        )
+        .unwrap();
+        let mut state = TilingState::default();
+        config.apply(&mut state);
+        let mut keymap = config.keymap.clone();
+        let mut dispatcher = Dispatcher::new();
+
+        write(&path, "[layout]\ndefault = \"sideways\"\n", 10);
+        assert!(Config::reload(&path, &mut state, &mut keymap, &mut dispatcher).is_err());
+        assert_eq!(state.layout, LayoutKind::Bsp);
+        assert_eq!(state.gaps.inner, 8.0);
+        assert_eq!(keymap, config.keymap);
+
+        write(&path, "[gaps]\ninner = 4\n", 20);
+        Config::reload(&path, &mut state, &mut keymap, &mut dispatcher).unwrap();
+        assert_eq!(state.layout, LayoutKind::Auto);
+        assert_eq!(state.gaps.inner, 4.0);
+        assert_eq!(keymap, Config::default().keymap);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn flip_takes_an_axis() {
+        let config = parse(
+            r#"
+[bindings]
+"ctrl+f" = "flip"
+"ctrl+h" = "flip-horizontal"
+"ctrl+v" = "flip-vertical"
+"#,
+        )
+        .unwrap();
+
+        let actions: Vec<&Action> = config.keymap.bindings().iter().map(|b| &b.action).collect();
+        assert_eq!(
+            actions,
+            [
+                &Action::FlipHorizontal,
+                &Action::FlipHorizontal,
+                &Action::FlipVertical
+            ]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! `osx-tiles doctor`: checks everything the daemon needs and says how to fix
+//! what is missing.
+
+use crate::backend::{Display, WindowBackend};
+use crate::config::Config;
+use crate::error::{ACCESSIBILITY_HELP, TileError};
+use std::path::Path;
+
+/// Prints the status of every prerequisite, one per line: `✓` passed, `✗`
+/// failed, `-` worth knowing. Returns whether nothing failed.
+pub fn run(backend: &mut dyn WindowBackend, config_path: Option<&Path>) -> bool {
+    let mut ok = true;
+    let mut fail = |message: String| {
+        println!("✗ {}", message);
+        ok = false;
+    };
+
+    if backend.is_trusted(false) {
+        println!("✓ Accessibility access granted");
+    } else {
+        fail("Accessibility access missing".to_string());
+        println!("{}", indent(ACCESSIBILITY_HELP));
+    }
+
+    match config_path {
+        None => println!("- HOME is not set, so no config file is read; using defaults"),
+        Some(path) if !path.exists() => {
+            println!("- No config file at {}, using defaults", path.display())
+        }
+        Some(path) => match Config::load(path) {
+            Ok(_) => println!("✓ Config file {} is valid", path.display()),
+            Err(e) => fail(format!("Invalid config: {}", e)),
+        },
+    }
+
+    match backend.displays() {
+        Ok(displays) if displays.is_empty() => fail(TileError::NoDisplay.to_string()),
+        Ok(displays) => {
+            println!(
+                "✓ {} display(s); [displays.<id>] in the config replaces their insets",
+                displays.len()
+            );
+            for (i, display) in displays.iter().enumerate() {
+                println!("    {}", describe_display(display, i == 0));
+            }
+        }
+        Err(e) => fail(format!("Cannot list displays: {}", e)),
+    }
+
+    match backend.visible_windows() {
+        Ok(windows) if windows.is_empty() => println!("- No windows to tile right now"),
+        Ok(windows) => println!("✓ {} window(s) can be tiled", windows.len()),
+        Err(e) => fail(format!("Cannot list windows: {}", e)),
+    }
+
+    match backend.focused_window() {
+        Ok(window) => println!("✓ Focused window: '{}' ({})", window.title, window.app_name),
+        Err(TileError::NoFocusedWindow) => println!("- No window has focus right now"),
+        Err(e) => fail(format!("Cannot read the focused window: {}", e)),
+    }
+
+    println!(
+        "- Hotkeys may also need Input Monitoring access (System Settings → \
+         Privacy & Security → Input Monitoring), which cannot be checked from here"
+    );
+
+    ok
+}
+
+/// One line with what the config needs to know about `display`.
+fn describe_display(display: &Display, main: bool) -> String {
+    let Display { id, bounds, insets } = display;
+    format!(
+        "id {}{}: {}x{} at ({}, {}), insets top {}, right {}, bottom {}, left {}",
+        id,
+        if main { " (main)" } else { "" },
+        bounds.width,
+        bounds.height,
+        bounds.x,
+        bounds.y,
+        insets.top,
+        insets.right,
+        insets.bottom,
+        insets.left
+    )
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MockBackend;
+    use crate::geometry::{Insets, Rect};
+
+    fn backend() -> MockBackend {
+        let mut backend = MockBackend::new().with_display(Rect::new(0.0, 0.0, 1440.0, 900.0));
+        backend.add_window("Window", Rect::new(100.0, 100.0, 400.0, 300.0));
+        backend
+    }
+
+    #[test]
+    fn passes_with_access() {
+        assert!(run(&mut backend(), None));
+        // No windows is worth knowing, not a failure
+        let mut backend = MockBackend::new().with_display(Rect::new(0.0, 0.0, 1440.0, 900.0));
+        assert!(run(&mut backend, None));
+    }
+
+    #[test]
+    fn fails_without_access_until_granted() {
+        let mut backend = backend().without_access();
+        assert!(!run(&mut backend, None));
+
+        backend.grant_access();
+        assert!(run(&mut backend, None));
+    }
+
+    #[test]
+    fn fails_without_a_display() {
+        assert!(!run(&mut MockBackend::new(), None));
+    }
+
+    #[test]
+    fn displays_are_described_by_id() {
+        let display = Display {
+            id: 69733378,
+            bounds: Rect::new(-1920.0, 0.0, 1920.0, 1080.0),
+            insets: Insets {
+                top: 25.0,
+                ..Insets::default()
+            },
+        };
+
+        assert_eq!(
+            describe_display(&display, false),
+            "id 69733378: 1920x1080 at (-1920, 0), insets top 25, right 0, bottom 0, left 0"
+        );
+        assert!(describe_display(&display, true).starts_with("id 69733378 (main): "));
+    }
+}
@@ -0,0 +1,216 @@
+//! Errors from window operations.
+
+use crate::backend::WindowId;
+use std::fmt;
+
+/// An `AXError` code returned by the Accessibility API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxError {
+    Failure,
+    IllegalArgument,
+    /// The element no longer exists, e.g. its window was closed.
+    InvalidElement,
+    InvalidObserver,
+    /// The app did not respond, e.g. because it is busy.
+    CannotComplete,
+    /// The element has no such attribute, or refuses to change it.
+    AttributeUnsupported,
+    ActionUnsupported,
+    NotificationUnsupported,
+    NotImplemented,
+    NotificationAlreadyRegistered,
+    NotificationNotRegistered,
+    /// The process is not trusted for accessibility.
+    ApiDisabled,
+    /// The attribute is supported but has no value right now.
+    NoValue,
+    ParameterizedAttributeUnsupported,
+    NotEnoughPrecision,
+    Other(i32),
+}
+
+impl AxError {
+    /// The error for a non-zero `AXError` code; `None` for `kAXErrorSuccess`.
+    pub fn from_code(code: i32) -> Option<Self> {
+        let error = match code {
+            0 => return None,
+            -25200 => AxError::Failure,
+            -25201 => AxError::IllegalArgument,
+            -25202 => AxError::InvalidElement,
+            -25203 => AxError::InvalidObserver,
+            -25204 => AxError::CannotComplete,
+            -25205 => AxError::AttributeUnsupported,
+            -25206 => AxError::ActionUnsupported,
+            -25207 => AxError::NotificationUnsupported,
+            -25208 => AxError::NotImplemented,
+            -25209 => AxError::NotificationAlreadyRegistered,
+            -25210 => AxError::NotificationNotRegistered,
+            -25211 => AxError::ApiDisabled,
+            -25212 => AxError::NoValue,
+            -25213 => AxError::ParameterizedAttributeUnsupported,
+            -25214 => AxError::NotEnoughPrecision,
+            code => AxError::Other(code),
+        };
+        Some(error)
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            AxError::Failure => -25200,
+            AxError::IllegalArgument => -25201,
+            AxError::InvalidElement => -25202,
+            AxError::InvalidObserver => -25203,
+            AxError::CannotComplete => -25204,
+            AxError::AttributeUnsupported => -25205,
+            AxError::ActionUnsupported => -25206,
+            AxError::NotificationUnsupported => -25207,
+            AxError::NotImplemented => -25208,
+            AxError::NotificationAlreadyRegistered => -25209,
+            AxError::NotificationNotRegistered => -25210,
+            AxError::ApiDisabled => -25211,
+            AxError::NoValue => -25212,
+            AxError::ParameterizedAttributeUnsupported => -25213,
+            AxError::NotEnoughPrecision => -25214,
+            AxError::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for AxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            AxError::Failure => "failed",
+            AxError::IllegalArgument => "illegal argument",
+            AxError::InvalidElement => "element no longer exists",
+            AxError::InvalidObserver => "invalid observer",
+            AxError::CannotComplete => "app did not respond",
+            AxError::AttributeUnsupported => "attribute not supported",
+            AxError::ActionUnsupported => "action not supported",
+            AxError::NotificationUnsupported => "notification not supported",
+            AxError::NotImplemented => "not implemented by the app",
+            AxError::NotificationAlreadyRegistered => "notification already registered",
+            AxError::NotificationNotRegistered => "notification not registered",
+            AxError::ApiDisabled => "accessibility access not granted",
+            AxError::NoValue => "no value",
+            AxError::ParameterizedAttributeUnsupported => "parameterized attribute not supported",
+            AxError::NotEnoughPrecision => "not enough precision",
+            AxError::Other(_) => "unknown error",
+        };
+        write!(f, "{} (AXError {})", description, self.code())
+    }
+}
+
+impl std::error::Error for AxError {}
+
+/// How to grant the accessibility access that moving windows needs.
+pub const ACCESSIBILITY_HELP: &str = "\
+To grant accessibility access:
+  1. Open System Settings → Privacy & Security → Accessibility
+  2. Turn on the terminal app running osx-tiles, or the osx-tiles binary
+     itself; add it with + if it is not listed
+  3. Restart osx-tiles, or set `wait-for-permission = true` in the config
+     to have it wait for access instead of exiting";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileError {
+    /// An Accessibility call reading or writing `attribute` failed, on
+    /// `window` if it concerned one.
+    Ax {
+        error: AxError,
+        attribute: &'static str,
+        window: Option<WindowId>,
+    },
+    /// Some other system query failed, such as listing displays.
+    System(String),
+    /// Accessibility access has not been granted.
+    NoAccess,
+    NoDisplay,
+    NoFocusedWindow,
+    NoWindows,
+    /// The window has closed, or was never seen by the backend.
+    UnknownWindow(WindowId),
+    /// A BSP action was run while another layout is active.
+    BspInactive,
+    /// The window, named by its title, has no BSP split to resize.
+    NoSplit(String),
+    /// The window, named by its title, was never moved by the daemon.
+    NotTiled(String),
+    NothingToRestore,
+    NothingToUndo,
+    NothingToRedo,
+}
+
+impl TileError {
+    pub fn ax(error: AxError, attribute: &'static str, window: Option<WindowId>) -> Self {
+        TileError::Ax {
+            error,
+            attribute,
+            window,
+        }
+    }
+
+    /// Whether the daemon lacks accessibility access, which no retry fixes
+    /// until the user grants it.
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(
+            self,
+            TileError::NoAccess
+                | TileError::Ax {
+                    error: AxError::ApiDisabled,
+                    ..
+                }
+        )
+    }
+
+    /// Whether this only means there was nothing for an action to do, as
+    /// opposed to something going wrong.
+    pub fn is_nothing_to_do(&self) -> bool {
+        matches!(
+            self,
+            TileError::NoFocusedWindow
+                | TileError::NoWindows
+                | TileError::NotTiled(_)
+                | TileError::NothingToRestore
+                | TileError::NothingToUndo
+                | TileError::NothingToRedo
+        )
+    }
+}
+
+impl fmt::Display for TileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileError::Ax {
+                error,
+                attribute,
+                window: Some(window),
+            } => write!(f, "{} of window {}: {}", attribute, window.0, error),
+            TileError::Ax {
+                error, attribute, ..
+            } => write!(f, "{}: {}", attribute, error),
+            TileError::System(message) => f.write_str(message),
+            TileError::NoAccess => {
+                f.write_str("osx-tiles needs accessibility access to find and move windows")
+            }
+            TileError::NoDisplay => f.write_str("No active display found"),
+            TileError::NoFocusedWindow => f.write_str("No focused window"),
+            TileError::NoWindows => f.write_str("No visible windows found"),
+            TileError::UnknownWindow(window) => write!(f, "Window {} does not exist", window.0),
+            TileError::BspInactive => f.write_str("BSP layout is not active"),
+            TileError::NoSplit(title) => write!(f, "Window '{}' has no split to resize", title),
+            TileError::NotTiled(title) => write!(f, "Window '{}' has not been tiled", title),
+            TileError::NothingToRestore => f.write_str("No tiled windows to restore"),
+            TileError::NothingToUndo => f.write_str("Nothing to undo"),
+            TileError::NothingToRedo => f.write_str("Nothing to redo"),
+        }
+    }
+}
+
+impl std::error::Error for TileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TileError::Ax { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
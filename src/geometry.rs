@@ -0,0 +1,115 @@
+use serde::Deserialize;
+
+/// An axis-aligned rectangle in global screen coordinates.
+///
+/// The origin is the top-left corner of the main display, matching the
+/// coordinate space used by the Accessibility API for window frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Distances from each edge of a rectangle, e.g. padding or reserved space.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Insets {
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub left: f64,
+}
+
+impl Insets {
+    pub fn uniform(inset: f64) -> Self {
+        Insets {
+            top: inset,
+            right: inset,
+            bottom: inset,
+            left: inset,
+        }
+    }
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Splits into a left part `ratio` of the width wide and the rest.
+    pub fn split_left_right(&self, ratio: f64) -> (Rect, Rect) {
+        let left_width = self.width * ratio;
+        (
+            Rect::new(self.x, self.y, left_width, self.height),
+            Rect::new(
+                self.x + left_width,
+                self.y,
+                self.width - left_width,
+                self.height,
+            ),
+        )
+    }
+
+    /// Splits into a top part `ratio` of the height tall and the rest.
+    pub fn split_top_bottom(&self, ratio: f64) -> (Rect, Rect) {
+        let top_height = self.height * ratio;
+        (
+            Rect::new(self.x, self.y, self.width, top_height),
+            Rect::new(
+                self.x,
+                self.y + top_height,
+                self.width,
+                self.height - top_height,
+            ),
+        )
+    }
+
+    /// Divides into `count` equally wide columns, left to right.
+    pub fn columns(&self, count: usize) -> Vec<Rect> {
+        let width = self.width / count as f64;
+        (0..count)
+            .map(|i| Rect::new(self.x + i as f64 * width, self.y, width, self.height))
+            .collect()
+    }
+
+    /// Divides into `count` equally tall rows, top to bottom.
+    pub fn rows(&self, count: usize) -> Vec<Rect> {
+        let height = self.height / count as f64;
+        (0..count)
+            .map(|i| Rect::new(self.x, self.y + i as f64 * height, self.width, height))
+            .collect()
+    }
+
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+
+    /// Shrinks the rectangle by `insets`, never below zero size.
+    pub fn inset(&self, insets: Insets) -> Rect {
+        Rect::new(
+            self.x + insets.left,
+            self.y + insets.top,
+            (self.width - insets.left - insets.right).max(0.0),
+            (self.height - insets.top - insets.bottom).max(0.0),
+        )
+    }
+
+    /// True when every edge is within `tolerance` of the other's.
+    pub fn approx_eq(&self, other: &Rect, tolerance: f64) -> bool {
+        (self.x - other.x).abs() <= tolerance
+            && (self.y - other.y).abs() <= tolerance
+            && (self.right() - other.right()).abs() <= tolerance
+            && (self.bottom() - other.bottom()).abs() <= tolerance
+    }
+}
@@ -0,0 +1,207 @@
+//! Undo and redo of window arrangements.
+//!
+//! [`History`] only stores frames; applying them to real windows is left to
+//! the caller, so it works the same with any backend.
+
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+use std::collections::VecDeque;
+
+/// How many arrangements are kept by default.
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// One arrangement: where every window it touched was before and after.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub before: Vec<(WindowId, Rect)>,
+    pub after: Vec<(WindowId, Rect)>,
+}
+
+impl Change {
+    fn touches_any(&self, open: &[WindowId]) -> bool {
+        self.before.iter().any(|(window, _)| open.contains(window))
+    }
+}
+
+/// Bounded undo and redo stacks of [`Change`]s, oldest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    /// A history that forgets the oldest arrangement beyond `limit`.
+    pub fn new(limit: usize) -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Adds an arrangement that was just applied. Anything undone before it
+    /// can no longer be redone. Changes that moved nothing are ignored.
+    pub fn record(&mut self, change: Change) {
+        if change.before == change.after {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(change);
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Steps back one arrangement and returns the frames to put the windows
+    /// in `open` back to. Windows that have closed since are left out, and
+    /// an arrangement whose windows have all closed is dropped in favour of
+    /// the one before it.
+    pub fn undo(&mut self, open: &[WindowId]) -> Option<Vec<(WindowId, Rect)>> {
+        while let Some(change) = self.undo.pop_back() {
+            if change.touches_any(open) {
+                let frames = still_open(&change.before, open);
+                self.redo.push(change);
+                return Some(frames);
+            }
+        }
+        None
+    }
+
+    /// Reapplies the last undone arrangement, like [`History::undo`] in
+    /// reverse.
+    pub fn redo(&mut self, open: &[WindowId]) -> Option<Vec<(WindowId, Rect)>> {
+        while let Some(change) = self.redo.pop() {
+            if change.touches_any(open) {
+                let frames = still_open(&change.after, open);
+                self.undo.push_back(change);
+                return Some(frames);
+            }
+        }
+        None
+    }
+}
+
+fn still_open(frames: &[(WindowId, Rect)], open: &[WindowId]) -> Vec<(WindowId, Rect)> {
+    frames
+        .iter()
+        .filter(|(window, _)| open.contains(window))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64) -> Rect {
+        Rect::new(x, 0.0, 100.0, 100.0)
+    }
+
+    /// `windows` moving from x = `from` to x = `to`.
+    fn change(windows: &[u32], from: f64, to: f64) -> Change {
+        Change {
+            before: windows.iter().map(|&w| (WindowId(w), rect(from))).collect(),
+            after: windows.iter().map(|&w| (WindowId(w), rect(to))).collect(),
+        }
+    }
+
+    const OPEN: &[WindowId] = &[WindowId(1), WindowId(2), WindowId(3)];
+
+    #[test]
+    fn undo_and_redo_walk_the_stacks() {
+        let mut history = History::default();
+        history.record(change(&[1], 0.0, 10.0));
+        history.record(change(&[1], 10.0, 20.0));
+
+        assert_eq!(history.undo(OPEN), Some(vec![(WindowId(1), rect(10.0))]));
+        assert_eq!(history.undo(OPEN), Some(vec![(WindowId(1), rect(0.0))]));
+        assert_eq!(history.undo(OPEN), None);
+        assert_eq!(history.redo(OPEN), Some(vec![(WindowId(1), rect(10.0))]));
+        assert_eq!(history.redo(OPEN), Some(vec![(WindowId(1), rect(20.0))]));
+        assert_eq!(history.redo(OPEN), None);
+    }
+
+    #[test]
+    fn limit_drops_the_oldest_change() {
+        let mut history = History::new(2);
+        history.record(change(&[1], 0.0, 10.0));
+        history.record(change(&[1], 10.0, 20.0));
+        history.record(change(&[1], 20.0, 30.0));
+
+        assert_eq!(history.undo(OPEN), Some(vec![(WindowId(1), rect(20.0))]));
+        assert_eq!(history.undo(OPEN), Some(vec![(WindowId(1), rect(10.0))]));
+        assert_eq!(history.undo(OPEN), None);
+    }
+
+    #[test]
+    fn record_clears_redo() {
+        let mut history = History::default();
+        history.record(change(&[1], 0.0, 10.0));
+        history.undo(OPEN);
+        assert!(history.can_redo());
+
+        history.record(change(&[2], 0.0, 50.0));
+
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(OPEN), None);
+    }
+
+    #[test]
+    fn no_op_changes_are_ignored() {
+        let mut history = History::default();
+        history.record(change(&[1], 0.0, 10.0));
+        history.undo(OPEN);
+
+        history.record(change(&[1], 10.0, 10.0));
+        history.record(change(&[], 0.0, 0.0));
+
+        assert!(!history.can_undo());
+        assert!(history.can_redo());
+    }
+
+    #[test]
+    fn undo_skips_closed_windows() {
+        let mut history = History::default();
+        history.record(change(&[1, 2, 3], 0.0, 10.0));
+
+        assert_eq!(
+            history.undo(&[WindowId(3), WindowId(1)]),
+            Some(vec![(WindowId(1), rect(0.0)), (WindowId(3), rect(0.0))])
+        );
+        assert_eq!(
+            history.redo(&[WindowId(3)]),
+            Some(vec![(WindowId(3), rect(10.0))])
+        );
+    }
+
+    #[test]
+    fn changes_to_closed_windows_are_dropped() {
+        let mut history = History::default();
+        history.record(change(&[1], 0.0, 10.0));
+        history.record(change(&[2, 3], 0.0, 20.0));
+        history.record(change(&[3], 20.0, 30.0));
+
+        let open = [WindowId(1)];
+        assert_eq!(history.undo(&open), Some(vec![(WindowId(1), rect(0.0))]));
+        assert!(!history.can_undo());
+        // Only the change that was undone can be redone
+        assert_eq!(history.redo(OPEN), Some(vec![(WindowId(1), rect(10.0))]));
+        assert!(!history.can_redo());
+    }
+}
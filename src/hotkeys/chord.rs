@@ -0,0 +1,428 @@
+use rdev::Key;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The four modifier kinds, each a pair of left and right keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Cmd,
+}
+
+impl Modifier {
+    /// In display order.
+    pub const ALL: [Modifier; 4] = [
+        Modifier::Ctrl,
+        Modifier::Alt,
+        Modifier::Shift,
+        Modifier::Cmd,
+    ];
+
+    /// The left and right keys. rdev reports the right Option key as `AltGr`.
+    pub fn keys(self) -> (Key, Key) {
+        match self {
+            Modifier::Ctrl => (Key::ControlLeft, Key::ControlRight),
+            Modifier::Alt => (Key::Alt, Key::AltGr),
+            Modifier::Shift => (Key::ShiftLeft, Key::ShiftRight),
+            Modifier::Cmd => (Key::MetaLeft, Key::MetaRight),
+        }
+    }
+
+    pub fn is_modifier_key(key: Key) -> bool {
+        Modifier::ALL.iter().any(|modifier| {
+            let (left, right) = modifier.keys();
+            key == left || key == right
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Modifier::Ctrl => "ctrl",
+            Modifier::Alt => "alt",
+            Modifier::Shift => "shift",
+            Modifier::Cmd => "cmd",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Modifier> {
+        let modifier = match name {
+            "ctrl" | "control" => Modifier::Ctrl,
+            "alt" | "option" | "opt" => Modifier::Alt,
+            "shift" => Modifier::Shift,
+            "cmd" | "command" | "super" => Modifier::Cmd,
+            _ => return None,
+        };
+        Some(modifier)
+    }
+}
+
+/// Which key of a modifier pair a chord asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Either,
+    Left,
+    Right,
+}
+
+impl Side {
+    fn is_held(self, left: bool, right: bool) -> bool {
+        match self {
+            Side::Either => left || right,
+            Side::Left => left && !right,
+            Side::Right => right && !left,
+        }
+    }
+
+    fn overlaps(self, other: Side) -> bool {
+        !matches!(
+            (self, other),
+            (Side::Left, Side::Right) | (Side::Right, Side::Left)
+        )
+    }
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Side::Either => "",
+            Side::Left => "l",
+            Side::Right => "r",
+        }
+    }
+}
+
+/// The modifiers a chord requires, each on a given side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers([Option<Side>; 4]);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers([None; 4]);
+
+    pub fn get(self, modifier: Modifier) -> Option<Side> {
+        self.0[modifier as usize]
+    }
+
+    pub fn with(mut self, modifier: Modifier, side: Side) -> Modifiers {
+        self.0[modifier as usize] = Some(side);
+        self
+    }
+
+    /// True when exactly these modifiers are held: each required one on an
+    /// accepted side and no others.
+    pub fn matches(self, pressed: &HashSet<Key>) -> bool {
+        Modifier::ALL.iter().all(|&modifier| {
+            let (left, right) = modifier.keys();
+            let (left, right) = (pressed.contains(&left), pressed.contains(&right));
+            match self.get(modifier) {
+                Some(side) => side.is_held(left, right),
+                None => !left && !right,
+            }
+        })
+    }
+
+    /// True when some combination of held keys satisfies both, e.g. `ctrl`
+    /// and `lctrl` overlap but `lctrl` and `rctrl` do not.
+    pub fn overlaps(self, other: Modifiers) -> bool {
+        Modifier::ALL.iter().all(
+            |&modifier| match (self.get(modifier), other.get(modifier)) {
+                (Some(a), Some(b)) => a.overlaps(b),
+                (a, b) => a.is_none() && b.is_none(),
+            },
+        )
+    }
+
+    fn union(self, other: Modifiers) -> Modifiers {
+        let mut out = self;
+        for modifier in Modifier::ALL {
+            if let Some(side) = other.get(modifier) {
+                out = out.with(modifier, side);
+            }
+        }
+        out
+    }
+
+    fn intersects(self, other: Modifiers) -> bool {
+        Modifier::ALL
+            .iter()
+            .any(|&modifier| self.get(modifier).is_some() && other.get(modifier).is_some())
+    }
+}
+
+/// A set of modifiers plus one trigger key, e.g. `ctrl+shift+t`.
+///
+/// Chords are normalized: `shift+ctrl+T` and `ctrl+shift+t` parse to the same
+/// value and display as the latter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+impl Chord {
+    /// Parses `+`-separated key names. Names are case-insensitive and may be
+    /// surrounded by spaces. `ctrl`, `alt`, `shift` and `cmd` (and their
+    /// aliases) accept either key of the pair; an `l` or `r` prefix, as in
+    /// `rctrl`, asks for one side. `hyper` means all four modifiers and `meh`
+    /// all but `cmd`.
+    pub fn parse(text: &str) -> Result<Chord, String> {
+        let mut modifiers = Modifiers::NONE;
+        let mut key = None;
+
+        for part in text.split('+') {
+            let name = part.trim().to_ascii_lowercase();
+            if name.is_empty() {
+                return Err(format!("empty key name in `{}`", text));
+            }
+
+            if let Some(modifier) = modifier_from_name(&name) {
+                if modifiers.intersects(modifier) {
+                    return Err(format!("`{}` repeats a modifier in `{}`", name, text));
+                }
+                modifiers = modifiers.union(modifier);
+            } else if let Some(named) = key_from_name(&name) {
+                if let Some(previous) = key {
+                    return Err(format!(
+                        "`{}` has two non-modifier keys (`{}` and `{}`); a chord has exactly one",
+                        text,
+                        key_name(previous),
+                        name
+                    ));
+                }
+                key = Some(named);
+            } else {
+                return Err(format!("unknown key `{}` in `{}`", part.trim(), text));
+            }
+        }
+
+        match key {
+            Some(key) => Ok(Chord { modifiers, key }),
+            None => Err(format!(
+                "`{}` has no key besides modifiers, e.g. `{}+t`",
+                text, text
+            )),
+        }
+    }
+
+    /// True when the trigger key is held together with exactly the chord's
+    /// modifiers.
+    pub fn is_pressed(&self, pressed: &HashSet<Key>) -> bool {
+        pressed.contains(&self.key) && self.modifiers.matches(pressed)
+    }
+
+    /// True when some key combination would trigger both chords.
+    pub fn overlaps(&self, other: &Chord) -> bool {
+        self.key == other.key && self.modifiers.overlaps(other.modifiers)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for modifier in Modifier::ALL {
+            if let Some(side) = self.modifiers.get(modifier) {
+                write!(f, "{}{}+", side.prefix(), modifier.name())?;
+            }
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+fn modifier_from_name(name: &str) -> Option<Modifiers> {
+    let all = |modifiers: &[Modifier]| {
+        modifiers
+            .iter()
+            .fold(Modifiers::NONE, |out, &m| out.with(m, Side::Either))
+    };
+    match name {
+        "hyper" => return Some(all(&Modifier::ALL)),
+        "meh" => return Some(all(&Modifier::ALL[..3])),
+        _ => {}
+    }
+
+    let (side, base) = if let Some(modifier) = Modifier::from_name(name) {
+        (Side::Either, modifier)
+    } else if let Some(modifier) = name.strip_prefix('l').and_then(Modifier::from_name) {
+        (Side::Left, modifier)
+    } else {
+        (
+            Side::Right,
+            name.strip_prefix('r').and_then(Modifier::from_name)?,
+        )
+    };
+    Some(Modifiers::NONE.with(base, side))
+}
+
+/// Canonical name first; further names are accepted aliases.
+const KEY_NAMES: &[(Key, &[&str])] = &[
+    (Key::KeyA, &["a"]),
+    (Key::KeyB, &["b"]),
+    (Key::KeyC, &["c"]),
+    (Key::KeyD, &["d"]),
+    (Key::KeyE, &["e"]),
+    (Key::KeyF, &["f"]),
+    (Key::KeyG, &["g"]),
+    (Key::KeyH, &["h"]),
+    (Key::KeyI, &["i"]),
+    (Key::KeyJ, &["j"]),
+    (Key::KeyK, &["k"]),
+    (Key::KeyL, &["l"]),
+    (Key::KeyM, &["m"]),
+    (Key::KeyN, &["n"]),
+    (Key::KeyO, &["o"]),
+    (Key::KeyP, &["p"]),
+    (Key::KeyQ, &["q"]),
+    (Key::KeyR, &["r"]),
+    (Key::KeyS, &["s"]),
+    (Key::KeyT, &["t"]),
+    (Key::KeyU, &["u"]),
+    (Key::KeyV, &["v"]),
+    (Key::KeyW, &["w"]),
+    (Key::KeyX, &["x"]),
+    (Key::KeyY, &["y"]),
+    (Key::KeyZ, &["z"]),
+    (Key::Num0, &["0"]),
+    (Key::Num1, &["1"]),
+    (Key::Num2, &["2"]),
+    (Key::Num3, &["3"]),
+    (Key::Num4, &["4"]),
+    (Key::Num5, &["5"]),
+    (Key::Num6, &["6"]),
+    (Key::Num7, &["7"]),
+    (Key::Num8, &["8"]),
+    (Key::Num9, &["9"]),
+    (Key::F1, &["f1"]),
+    (Key::F2, &["f2"]),
+    (Key::F3, &["f3"]),
+    (Key::F4, &["f4"]),
+    (Key::F5, &["f5"]),
+    (Key::F6, &["f6"]),
+    (Key::F7, &["f7"]),
+    (Key::F8, &["f8"]),
+    (Key::F9, &["f9"]),
+    (Key::F10, &["f10"]),
+    (Key::F11, &["f11"]),
+    (Key::F12, &["f12"]),
+    (Key::LeftArrow, &["left"]),
+    (Key::RightArrow, &["right"]),
+    (Key::UpArrow, &["up"]),
+    (Key::DownArrow, &["down"]),
+    (Key::Space, &["space"]),
+    (Key::Tab, &["tab"]),
+    (Key::Return, &["return", "enter"]),
+    (Key::Escape, &["escape", "esc"]),
+    (Key::Backspace, &["backspace"]),
+    (Key::Delete, &["delete"]),
+    (Key::Home, &["home"]),
+    (Key::End, &["end"]),
+    (Key::PageUp, &["pageup"]),
+    (Key::PageDown, &["pagedown"]),
+    (Key::Minus, &["minus", "-"]),
+    (Key::Equal, &["equal", "="]),
+    (Key::LeftBracket, &["leftbracket", "["]),
+    (Key::RightBracket, &["rightbracket", "]"]),
+    (Key::SemiColon, &["semicolon", ";"]),
+    (Key::Quote, &["quote", "'"]),
+    (Key::BackQuote, &["backquote", "`"]),
+    (Key::BackSlash, &["backslash", "\\"]),
+    (Key::Comma, &["comma", ","]),
+    (Key::Dot, &["period", "."]),
+    (Key::Slash, &["slash", "/"]),
+];
+
+fn key_from_name(name: &str) -> Option<Key> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|&(key, _)| key)
+}
+
+fn key_name(key: Key) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|&&(named, _)| named == key)
+        .map_or("?", |(_, names)| names[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> Chord {
+        Chord::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_normalizes_order_case_and_spaces() {
+        assert_eq!(chord("Shift+Ctrl+T"), chord("ctrl+shift+t"));
+        assert_eq!(chord(" ctrl + SHIFT + t "), chord("ctrl+shift+t"));
+        assert_eq!(chord("Shift+Ctrl+T").to_string(), "ctrl+shift+t");
+        assert_eq!(chord("option+command+Enter").to_string(), "alt+cmd+return");
+    }
+
+    #[test]
+    fn parse_reads_modifier_sides() {
+        let parsed = chord("rctrl+lshift+alt+x");
+
+        assert_eq!(parsed.modifiers.get(Modifier::Ctrl), Some(Side::Right));
+        assert_eq!(parsed.modifiers.get(Modifier::Shift), Some(Side::Left));
+        assert_eq!(parsed.modifiers.get(Modifier::Alt), Some(Side::Either));
+        assert_eq!(parsed.modifiers.get(Modifier::Cmd), None);
+        assert_eq!(parsed.key, Key::KeyX);
+        assert_eq!(parsed.to_string(), "rctrl+alt+lshift+x");
+    }
+
+    #[test]
+    fn parse_expands_hyper_and_meh() {
+        assert_eq!(chord("hyper+h"), chord("ctrl+alt+shift+cmd+h"));
+        assert_eq!(chord("meh+m"), chord("ctrl+alt+shift+m"));
+        assert_eq!(chord("hyper+h").to_string(), "ctrl+alt+shift+cmd+h");
+    }
+
+    #[test]
+    fn parse_accepts_punctuation_keys() {
+        assert_eq!(chord("cmd+,").key, Key::Comma);
+        assert_eq!(chord("ctrl+-"), chord("ctrl+minus"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_keys() {
+        assert_eq!(
+            Chord::parse("ctrl+foo"),
+            Err("unknown key `foo` in `ctrl+foo`".to_string())
+        );
+        assert_eq!(
+            Chord::parse("ctrl++t"),
+            Err("empty key name in `ctrl++t`".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_rejects_modifiers_without_key() {
+        assert_eq!(
+            Chord::parse("ctrl+shift"),
+            Err("`ctrl+shift` has no key besides modifiers, e.g. `ctrl+shift+t`".to_string())
+        );
+        assert!(Chord::parse("hyper").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_two_keys() {
+        assert_eq!(
+            Chord::parse("ctrl+a+b"),
+            Err(
+                "`ctrl+a+b` has two non-modifier keys (`a` and `b`); a chord has exactly one"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_rejects_repeated_modifiers() {
+        assert_eq!(
+            Chord::parse("ctrl+control+t"),
+            Err("`control` repeats a modifier in `ctrl+control+t`".to_string())
+        );
+        assert!(Chord::parse("lctrl+rctrl+t").is_err());
+        assert!(Chord::parse("hyper+shift+t").is_err());
+        assert!(Chord::parse("meh+cmd+t").is_ok());
+    }
+}
@@ -0,0 +1,744 @@
+use super::{Binding, Chord, ChordTracker, Keymap, Stroke, TrieNode};
+use crate::actions::Action;
+use rdev::{EventType, Key};
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// What handling one event amounted to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome<'a> {
+    /// The binding the event completed, if any.
+    pub binding: Option<&'a Binding>,
+    /// Whether the event belongs to a binding and should be kept from the
+    /// focused app: strokes that match a binding or part of a sequence, and
+    /// their key repeats and releases. Modifiers and unbound keys always pass
+    /// through.
+    pub consume: bool,
+}
+
+/// Routes key events to the bindings of the current mode.
+///
+/// Modes form a stack: entering a mode pushes it, `exit-mode` pops back to
+/// the previous one, and only the innermost mode's bindings are live. A mode
+/// with a timeout is left once that long passes without one of its bindings
+/// firing while it is current.
+///
+/// Sequences are matched one stroke at a time against the mode's trie. A
+/// stroke that matches nothing, or waiting longer than the keymap's sequence
+/// timeout, abandons a half-typed sequence.
+#[derive(Debug, Clone, Default)]
+pub struct Dispatcher {
+    keys: ChordTracker,
+    /// Entered modes, innermost last. Only the innermost one's deadline is
+    /// running.
+    modes: Vec<(String, Option<Instant>)>,
+    /// Chords of the sequence typed so far, and when the last one was.
+    pending: Vec<Chord>,
+    last_stroke: Option<Instant>,
+    /// The full sequence of the binding that fired last, while its final
+    /// chord may still be auto-repeating.
+    last_fired: Option<Vec<Chord>>,
+    /// Keys whose press was consumed, so their repeats and release are too.
+    consumed: HashSet<Key>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current mode, or `None` for the default bindings.
+    pub fn mode(&self) -> Option<&str> {
+        self.modes.last().map(|(name, _)| name.as_str())
+    }
+
+    /// The strokes of a sequence waiting for its next one.
+    pub fn pending(&self) -> &[Chord] {
+        &self.pending
+    }
+
+    /// Returns to the default bindings and drops any half-typed sequence,
+    /// e.g. after the keymap was reloaded.
+    pub fn reset_modes(&mut self) {
+        self.modes.clear();
+        self.pending.clear();
+        self.last_fired = None;
+    }
+
+    /// Records `event`, received at `now`, and returns the binding it fires
+    /// and whether to consume it. A mode change it asks for has already been
+    /// made.
+    pub fn handle<'a>(
+        &mut self,
+        event: &EventType,
+        now: Instant,
+        keymap: &'a Keymap,
+    ) -> Outcome<'a> {
+        self.expire(now, keymap);
+        if self
+            .last_stroke
+            .is_some_and(|last| now.saturating_duration_since(last) > keymap.sequence_timeout())
+        {
+            self.pending.clear();
+        }
+
+        let released = match *event {
+            EventType::KeyRelease(key) => self.consumed.remove(&key),
+            _ => false,
+        };
+        let Some(stroke) = self.keys.handle(event, now) else {
+            return Outcome {
+                binding: None,
+                consume: released,
+            };
+        };
+        self.last_stroke = Some(now);
+
+        let (fired, consume) = if stroke.repeat {
+            let fired = self.repeated(&stroke, keymap);
+            (fired, self.consumed.contains(&stroke.key))
+        } else {
+            let fired = self.advance(&stroke, keymap);
+            // An unmatched stroke leaves nothing pending
+            let matched = fired.is_some() || !self.pending.is_empty();
+            if matched {
+                self.consumed.insert(stroke.key);
+            } else {
+                self.consumed.remove(&stroke.key);
+            }
+            (fired, matched)
+        };
+
+        let Some((sequence, binding)) = fired else {
+            return Outcome {
+                binding: None,
+                consume,
+            };
+        };
+
+        // Only bindings of the mode that stays current can repeat
+        self.last_fired = None;
+        match &binding.action {
+            Action::EnterMode(name) if keymap.mode(name).is_some() => {
+                self.modes.push((name.clone(), None));
+            }
+            Action::ExitMode => {
+                self.modes.pop();
+            }
+            _ => self.last_fired = Some(sequence),
+        }
+        self.restart_timer(now, keymap);
+        Outcome {
+            binding: Some(binding),
+            consume,
+        }
+    }
+
+    /// Moves one stroke down the current mode's trie.
+    fn advance<'a>(
+        &mut self,
+        stroke: &Stroke,
+        keymap: &'a Keymap,
+    ) -> Option<(Vec<Chord>, &'a Binding)> {
+        self.last_fired = None;
+        // Borrows only `modes`, leaving `pending` free to change
+        let mode = self.modes.last().map(|(name, _)| name.as_str());
+        let root = keymap.trie_for(mode);
+        let level = match root.walk(&self.pending) {
+            Some(TrieNode::Prefix(next)) => next,
+            _ => {
+                self.pending.clear();
+                root
+            }
+        };
+
+        let Some((chord, node)) = level.step(stroke.key, &stroke.held) else {
+            self.pending.clear();
+            return None;
+        };
+        self.pending.push(chord);
+
+        match node {
+            TrieNode::Prefix(_) => None,
+            TrieNode::Binding(index) => {
+                let sequence = std::mem::take(&mut self.pending);
+                Some((sequence, &keymap.bindings_for(mode)[*index]))
+            }
+        }
+    }
+
+    /// Fires the last binding again if it repeats and its final chord is
+    /// the one auto-repeating.
+    fn repeated<'a>(
+        &self,
+        stroke: &Stroke,
+        keymap: &'a Keymap,
+    ) -> Option<(Vec<Chord>, &'a Binding)> {
+        let sequence = self.last_fired.as_ref()?;
+        let last = sequence.last()?;
+        if last.key != stroke.key || !last.is_pressed(&stroke.held) {
+            return None;
+        }
+        let mode = self.mode();
+        match keymap.trie_for(mode).walk(sequence)? {
+            TrieNode::Binding(index) => {
+                let binding = &keymap.bindings_for(mode)[*index];
+                binding.repeat.then(|| (sequence.clone(), binding))
+            }
+            TrieNode::Prefix(_) => None,
+        }
+    }
+
+    /// Pops every mode whose timeout ran out before `now`. A mode's timer
+    /// starts when it becomes current again.
+    fn expire(&mut self, now: Instant, keymap: &Keymap) {
+        while let Some(&(_, Some(deadline))) = self.modes.last() {
+            if now < deadline {
+                break;
+            }
+            self.modes.pop();
+            self.pending.clear();
+            self.last_fired = None;
+            self.restart_timer(deadline, keymap);
+        }
+    }
+
+    fn restart_timer(&mut self, from: Instant, keymap: &Keymap) {
+        if let Some((name, deadline)) = self.modes.last_mut() {
+            *deadline = keymap
+                .mode(name)
+                .and_then(|mode| mode.timeout)
+                .map(|timeout| from + timeout);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotkeys::{DEFAULT_SEQUENCE_TIMEOUT, Mode};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn press(dispatcher: &mut Dispatcher, keymap: &Keymap, key: Key) -> (Option<Action>, bool) {
+        let outcome = dispatcher.handle(&EventType::KeyPress(key), Instant::now(), keymap);
+        (outcome.binding.map(|b| b.action.clone()), outcome.consume)
+    }
+
+    fn release(dispatcher: &mut Dispatcher, keymap: &Keymap, key: Key) -> bool {
+        dispatcher
+            .handle(&EventType::KeyRelease(key), Instant::now(), keymap)
+            .consume
+    }
+
+    fn keymap(bindings: &[(&str, Action)]) -> Keymap {
+        let bindings = bindings
+            .iter()
+            .map(|(sequence, action)| Binding::parse(sequence, action.clone()).unwrap())
+            .collect();
+        Keymap::new(bindings, HashMap::new(), DEFAULT_SEQUENCE_TIMEOUT)
+    }
+
+    /// A mode's name, bindings and timeout in seconds.
+    type TestMode<'a> = (&'a str, &'a [(&'a str, Action)], Option<u64>);
+
+    /// A keymap with `bindings` by default and `modes`.
+    fn keymap_with_modes(bindings: &[(&str, Action)], modes: &[TestMode]) -> Keymap {
+        let parse = |bindings: &[(&str, Action)]| -> Vec<Binding> {
+            bindings
+                .iter()
+                .map(|(sequence, action)| Binding::parse(sequence, action.clone()).unwrap())
+                .collect()
+        };
+        let modes = modes
+            .iter()
+            .map(|&(name, bindings, timeout)| {
+                let mode = Mode {
+                    bindings: parse(bindings),
+                    timeout: timeout.map(Duration::from_secs),
+                };
+                (name.to_string(), mode)
+            })
+            .collect();
+        Keymap::new(parse(bindings), modes, DEFAULT_SEQUENCE_TIMEOUT)
+    }
+
+    /// Presses `keys` in order at `now`, then releases them, and returns
+    /// the action fired and whether each press was consumed.
+    fn chord_at(
+        dispatcher: &mut Dispatcher,
+        keymap: &Keymap,
+        keys: &[Key],
+        now: Instant,
+    ) -> (Option<Action>, Vec<bool>) {
+        let mut fired = None;
+        let mut consumed = Vec::new();
+        for &key in keys {
+            let outcome = dispatcher.handle(&EventType::KeyPress(key), now, keymap);
+            fired = fired.or(outcome.binding.map(|b| b.action.clone()));
+            consumed.push(outcome.consume);
+        }
+        for &key in keys.iter().rev() {
+            dispatcher.handle(&EventType::KeyRelease(key), now, keymap);
+        }
+        (fired, consumed)
+    }
+
+    fn fire_at(
+        dispatcher: &mut Dispatcher,
+        keymap: &Keymap,
+        keys: &[Key],
+        now: Instant,
+    ) -> Option<Action> {
+        chord_at(dispatcher, keymap, keys, now).0
+    }
+
+    fn secs(start: Instant, seconds: u64) -> Instant {
+        start + Duration::from_secs(seconds)
+    }
+
+    /// Presses `keys` in order, then releases them all, and returns the
+    /// actions fired along the way.
+    fn type_keys(keymap: &Keymap, keys: &[Key]) -> Vec<Action> {
+        let mut dispatcher = Dispatcher::new();
+        let fired = keys
+            .iter()
+            .filter_map(|&key| press(&mut dispatcher, keymap, key).0)
+            .collect();
+        for &key in keys.iter().rev() {
+            release(&mut dispatcher, keymap, key);
+        }
+        fired
+    }
+
+    #[test]
+    fn plain_modifier_matches_either_side() {
+        let keymap = keymap(&[("ctrl+t", Action::TileLeft)]);
+
+        assert_eq!(
+            type_keys(&keymap, &[Key::ControlLeft, Key::KeyT]),
+            vec![Action::TileLeft]
+        );
+        assert_eq!(
+            type_keys(&keymap, &[Key::ControlRight, Key::KeyT]),
+            vec![Action::TileLeft]
+        );
+        assert_eq!(
+            type_keys(&keymap, &[Key::ControlLeft, Key::ControlRight, Key::KeyT]),
+            vec![Action::TileLeft]
+        );
+    }
+
+    #[test]
+    fn sided_modifier_rejects_the_other_key() {
+        let keymap = keymap(&[("rctrl+t", Action::TileRight)]);
+
+        assert_eq!(
+            type_keys(&keymap, &[Key::ControlRight, Key::KeyT]),
+            vec![Action::TileRight]
+        );
+        assert!(type_keys(&keymap, &[Key::ControlLeft, Key::KeyT]).is_empty());
+        assert!(type_keys(&keymap, &[Key::ControlLeft, Key::ControlRight, Key::KeyT]).is_empty());
+    }
+
+    #[test]
+    fn right_option_is_reported_as_alt_gr() {
+        let keymap = keymap(&[
+            ("ralt+rshift+x", Action::Center),
+            ("lalt+x", Action::Maximize),
+        ]);
+
+        assert_eq!(
+            type_keys(&keymap, &[Key::AltGr, Key::ShiftRight, Key::KeyX]),
+            vec![Action::Center]
+        );
+        assert_eq!(
+            type_keys(&keymap, &[Key::Alt, Key::KeyX]),
+            vec![Action::Maximize]
+        );
+        assert!(type_keys(&keymap, &[Key::AltGr, Key::KeyX]).is_empty());
+        assert!(type_keys(&keymap, &[Key::AltGr, Key::ShiftLeft, Key::KeyX]).is_empty());
+    }
+
+    #[test]
+    fn extra_modifiers_prevent_a_match() {
+        let keymap = keymap(&[("ctrl+t", Action::TileLeft)]);
+
+        assert!(type_keys(&keymap, &[Key::ControlRight, Key::ShiftRight, Key::KeyT]).is_empty());
+        assert!(type_keys(&keymap, &[Key::KeyT]).is_empty());
+    }
+
+    #[test]
+    fn consumes_bound_stroke_with_its_repeats_and_release() {
+        let keymap = Keymap::default();
+        let mut dispatcher = Dispatcher::new();
+
+        assert_eq!(
+            press(&mut dispatcher, &keymap, Key::ControlLeft),
+            (None, false)
+        );
+        assert_eq!(
+            press(&mut dispatcher, &keymap, Key::ShiftLeft),
+            (None, false)
+        );
+        assert_eq!(
+            press(&mut dispatcher, &keymap, Key::KeyT),
+            (Some(Action::TileLeft), true)
+        );
+        // Held down: a repeat is consumed but does not fire again
+        assert_eq!(press(&mut dispatcher, &keymap, Key::KeyT), (None, true));
+        assert!(release(&mut dispatcher, &keymap, Key::KeyT));
+        assert!(!release(&mut dispatcher, &keymap, Key::ShiftLeft));
+        assert!(!release(&mut dispatcher, &keymap, Key::ControlLeft));
+    }
+
+    #[test]
+    fn passes_unbound_keys_through() {
+        let keymap = Keymap::default();
+        let mut dispatcher = Dispatcher::new();
+
+        assert_eq!(press(&mut dispatcher, &keymap, Key::KeyT), (None, false));
+        assert_eq!(press(&mut dispatcher, &keymap, Key::KeyT), (None, false));
+        assert!(!release(&mut dispatcher, &keymap, Key::KeyT));
+
+        press(&mut dispatcher, &keymap, Key::ControlLeft);
+        assert_eq!(press(&mut dispatcher, &keymap, Key::KeyT), (None, false));
+        assert!(!release(&mut dispatcher, &keymap, Key::KeyT));
+    }
+
+    #[test]
+    fn repeating_binding_fires_on_every_repeat() {
+        let keymap = Keymap::default();
+        let mut dispatcher = Dispatcher::new();
+
+        press(&mut dispatcher, &keymap, Key::ControlLeft);
+        press(&mut dispatcher, &keymap, Key::ShiftLeft);
+        for _ in 0..3 {
+            assert_eq!(
+                press(&mut dispatcher, &keymap, Key::KeyL),
+                (Some(Action::GrowRatio), true)
+            );
+        }
+        assert!(release(&mut dispatcher, &keymap, Key::KeyL));
+    }
+
+    fn resize_keymap() -> Keymap {
+        keymap_with_modes(
+            &[
+                ("ctrl+r", Action::EnterMode("resize".to_string())),
+                ("ctrl+n", Action::EnterMode("nowhere".to_string())),
+                ("h", Action::TileLeft),
+            ],
+            &[
+                (
+                    "resize",
+                    &[
+                        ("h", Action::ShrinkRatio),
+                        ("m", Action::EnterMode("move".to_string())),
+                        ("escape", Action::ExitMode),
+                    ],
+                    Some(5),
+                ),
+                (
+                    "move",
+                    &[("l", Action::TileRight), ("q", Action::ExitMode)],
+                    None,
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn mode_replaces_the_default_bindings() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], now),
+            Some(Action::TileLeft)
+        );
+        assert_eq!(
+            fire_at(
+                &mut dispatcher,
+                &keymap,
+                &[Key::ControlLeft, Key::KeyR],
+                now
+            ),
+            Some(Action::EnterMode("resize".to_string()))
+        );
+        assert_eq!(dispatcher.mode(), Some("resize"));
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], now),
+            Some(Action::ShrinkRatio)
+        );
+        // Default bindings are off while the mode is active
+        assert_eq!(
+            fire_at(
+                &mut dispatcher,
+                &keymap,
+                &[Key::ControlLeft, Key::KeyR],
+                now
+            ),
+            None
+        );
+
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::Escape], now),
+            Some(Action::ExitMode)
+        );
+        assert_eq!(dispatcher.mode(), None);
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], now),
+            Some(Action::TileLeft)
+        );
+    }
+
+    #[test]
+    fn nested_modes_pop_back_one_at_a_time() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::KeyR],
+            now,
+        );
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyM], now);
+        assert_eq!(dispatcher.mode(), Some("move"));
+        // Only the innermost mode's bindings are live
+        assert_eq!(fire_at(&mut dispatcher, &keymap, &[Key::KeyH], now), None);
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyL], now),
+            Some(Action::TileRight)
+        );
+
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyQ], now);
+        assert_eq!(dispatcher.mode(), Some("resize"));
+        fire_at(&mut dispatcher, &keymap, &[Key::Escape], now);
+        assert_eq!(dispatcher.mode(), None);
+    }
+
+    #[test]
+    fn unknown_mode_is_not_entered() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::KeyN],
+            Instant::now(),
+        );
+
+        assert_eq!(dispatcher.mode(), None);
+    }
+
+    #[test]
+    fn mode_times_out_without_a_binding_firing() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let start = Instant::now();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::KeyR],
+            start,
+        );
+        // Each binding that fires restarts the timeout
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], secs(start, 4)),
+            Some(Action::ShrinkRatio)
+        );
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], secs(start, 8)),
+            Some(Action::ShrinkRatio)
+        );
+        // Unbound keys do not
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyX], secs(start, 12));
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], secs(start, 14)),
+            Some(Action::TileLeft)
+        );
+        assert_eq!(dispatcher.mode(), None);
+    }
+
+    #[test]
+    fn timeout_only_runs_while_the_mode_is_current() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let start = Instant::now();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::KeyR],
+            start,
+        );
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyM], secs(start, 1));
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyX], secs(start, 30));
+        assert_eq!(dispatcher.mode(), Some("move"));
+
+        // Back in resize, its timer starts over
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyQ], secs(start, 30));
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyX], secs(start, 34));
+        assert_eq!(dispatcher.mode(), Some("resize"));
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyX], secs(start, 36));
+        assert_eq!(dispatcher.mode(), None);
+    }
+
+    #[test]
+    fn reset_modes_returns_to_the_default_bindings() {
+        let keymap = resize_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::KeyR],
+            now,
+        );
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyM], now);
+
+        dispatcher.reset_modes();
+
+        assert_eq!(dispatcher.mode(), None);
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyH], now),
+            Some(Action::TileLeft)
+        );
+    }
+
+    fn sequence_keymap() -> Keymap {
+        keymap(&[
+            ("ctrl+space, w, l", Action::TileLeft),
+            ("ctrl+space, w, r", Action::TileRight),
+            ("ctrl+space, a", Action::AutoArrange),
+            ("x", Action::Center),
+        ])
+    }
+
+    #[test]
+    fn sequence_fires_on_its_last_stroke() {
+        let keymap = sequence_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+
+        assert_eq!(
+            fire_at(
+                &mut dispatcher,
+                &keymap,
+                &[Key::ControlLeft, Key::Space],
+                now
+            ),
+            None
+        );
+        assert_eq!(fire_at(&mut dispatcher, &keymap, &[Key::KeyW], now), None);
+        assert_eq!(dispatcher.pending().len(), 2);
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyR], now),
+            Some(Action::TileRight)
+        );
+        assert!(dispatcher.pending().is_empty());
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::Space],
+            now,
+        );
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyA], now),
+            Some(Action::AutoArrange)
+        );
+    }
+
+    #[test]
+    fn sequence_strokes_are_consumed() {
+        let keymap = sequence_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+
+        let (_, consumed) = chord_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::Space],
+            now,
+        );
+        assert_eq!(consumed, vec![false, true]);
+        assert_eq!(
+            chord_at(&mut dispatcher, &keymap, &[Key::KeyW], now).1,
+            vec![true]
+        );
+        assert_eq!(
+            chord_at(&mut dispatcher, &keymap, &[Key::KeyL], now),
+            (Some(Action::TileLeft), vec![true])
+        );
+        // Unbound keys go through once nothing is pending
+        assert_eq!(
+            chord_at(&mut dispatcher, &keymap, &[Key::KeyW], now).1,
+            vec![false]
+        );
+    }
+
+    #[test]
+    fn unmatched_stroke_abandons_the_sequence() {
+        let keymap = sequence_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let now = Instant::now();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::Space],
+            now,
+        );
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyW], now);
+        let (fired, consumed) = chord_at(&mut dispatcher, &keymap, &[Key::KeyQ], now);
+
+        assert_eq!((fired, consumed), (None, vec![false]));
+        assert!(dispatcher.pending().is_empty());
+        assert_eq!(fire_at(&mut dispatcher, &keymap, &[Key::KeyL], now), None);
+        assert_eq!(
+            fire_at(&mut dispatcher, &keymap, &[Key::KeyX], now),
+            Some(Action::Center)
+        );
+    }
+
+    #[test]
+    fn sequence_times_out_between_strokes() {
+        let keymap = sequence_keymap();
+        let mut dispatcher = Dispatcher::new();
+        let start = Instant::now();
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::Space],
+            start,
+        );
+        assert_eq!(
+            fire_at(
+                &mut dispatcher,
+                &keymap,
+                &[Key::KeyA],
+                start + Duration::from_millis(900)
+            ),
+            Some(Action::AutoArrange)
+        );
+
+        fire_at(
+            &mut dispatcher,
+            &keymap,
+            &[Key::ControlLeft, Key::Space],
+            secs(start, 5),
+        );
+        fire_at(&mut dispatcher, &keymap, &[Key::KeyW], secs(start, 6));
+        let (fired, consumed) = chord_at(&mut dispatcher, &keymap, &[Key::KeyL], secs(start, 8));
+        assert_eq!((fired, consumed), (None, vec![false]));
+        assert!(dispatcher.pending().is_empty());
+    }
+}
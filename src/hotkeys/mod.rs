@@ -0,0 +1,225 @@
+//! Key bindings: which chords, or sequences of chords, trigger which action.
+
+mod chord;
+mod dispatcher;
+mod sequence;
+mod tracker;
+
+pub use chord::{Chord, Modifier, Modifiers, Side};
+pub use dispatcher::{Dispatcher, Outcome};
+pub use sequence::{Sequence, Trie, TrieNode};
+pub use tracker::{ChordTracker, Stroke};
+
+use crate::actions::Action;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Bindings used when the config file has no `[bindings]` table.
+pub const DEFAULT_BINDINGS: &[(&str, Action)] = &[
+    ("ctrl+shift+t", Action::TileLeft),
+    ("ctrl+shift+a", Action::AutoArrange),
+    ("ctrl+shift+h", Action::ShrinkRatio),
+    ("ctrl+shift+l", Action::GrowRatio),
+    ("ctrl+shift+d", Action::DecreaseMasters),
+    ("ctrl+shift+i", Action::IncreaseMasters),
+    ("ctrl+shift+b", Action::ToggleBsp),
+    ("ctrl+shift+r", Action::Rotate),
+    ("ctrl+shift+f", Action::FlipHorizontal),
+    ("ctrl+shift+v", Action::FlipVertical),
+    ("ctrl+shift+e", Action::Balance),
+    ("ctrl+shift+z", Action::Undo),
+    ("ctrl+shift+y", Action::Redo),
+    ("ctrl+shift+q", Action::Quit),
+    ("ctrl+alt+left", Action::TileLeft),
+    ("ctrl+alt+right", Action::TileRight),
+    ("ctrl+alt+up", Action::TileTop),
+    ("ctrl+alt+down", Action::TileBottom),
+    ("ctrl+alt+u", Action::TileTopLeft),
+    ("ctrl+alt+i", Action::TileTopRight),
+    ("ctrl+alt+j", Action::TileBottomLeft),
+    ("ctrl+alt+k", Action::TileBottomRight),
+    ("ctrl+alt+d", Action::TileLeftThird),
+    ("ctrl+alt+f", Action::TileCenterThird),
+    ("ctrl+alt+g", Action::TileRightThird),
+    ("ctrl+alt+e", Action::TileLeftTwoThirds),
+    ("ctrl+alt+t", Action::TileRightTwoThirds),
+    ("ctrl+alt+c", Action::Center),
+    ("ctrl+alt+return", Action::Maximize),
+    ("ctrl+alt+backspace", Action::Restore),
+    ("ctrl+shift+backspace", Action::RestoreAll),
+];
+
+/// Default bindings that fire again on key repeat while held.
+const DEFAULT_REPEATABLE: &[Action] = &[Action::ShrinkRatio, Action::GrowRatio];
+
+/// How long a sequence waits for its next stroke by default.
+pub const DEFAULT_SEQUENCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub sequence: Sequence,
+    pub action: Action,
+    /// Fire again on every key repeat while the last chord is held, for
+    /// actions that step, like resizing.
+    pub repeat: bool,
+}
+
+impl Binding {
+    /// Parses a chord such as `ctrl+shift+t` or a sequence such as
+    /// `ctrl+space, w, l`; see [`Sequence::parse`].
+    pub fn parse(sequence: &str, action: Action) -> Result<Self, String> {
+        Ok(Binding {
+            sequence: Sequence::parse(sequence)?,
+            action,
+            repeat: false,
+        })
+    }
+
+    pub fn repeating(self, repeat: bool) -> Self {
+        Binding { repeat, ..self }
+    }
+}
+
+/// A named set of bindings that replaces the default ones while active.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mode {
+    pub bindings: Vec<Binding>,
+    /// Leave the mode after this long without one of its bindings firing.
+    pub timeout: Option<Duration>,
+}
+
+/// The default bindings plus every named mode, indexed for lookup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    modes: HashMap<String, Mode>,
+    sequence_timeout: Duration,
+    /// One trie per binding table: the default one under `None`, modes under
+    /// their names.
+    tries: HashMap<Option<String>, Trie>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(default_bindings(), HashMap::new(), DEFAULT_SEQUENCE_TIMEOUT)
+    }
+}
+
+impl Keymap {
+    /// `sequence_timeout` is how long a sequence waits for its next stroke.
+    pub fn new(
+        bindings: Vec<Binding>,
+        modes: HashMap<String, Mode>,
+        sequence_timeout: Duration,
+    ) -> Self {
+        let mut tries = HashMap::new();
+        tries.insert(None, Trie::new(&bindings));
+        for (name, mode) in &modes {
+            tries.insert(Some(name.clone()), Trie::new(&mode.bindings));
+        }
+        Keymap {
+            bindings,
+            modes,
+            sequence_timeout,
+            tries,
+        }
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    pub fn mode(&self, name: &str) -> Option<&Mode> {
+        self.modes.get(name)
+    }
+
+    pub fn sequence_timeout(&self) -> Duration {
+        self.sequence_timeout
+    }
+
+    /// The bindings live in `mode`, or the default ones for `None` or a mode
+    /// that does not exist.
+    pub fn bindings_for(&self, mode: Option<&str>) -> &[Binding] {
+        mode.and_then(|name| self.mode(name))
+            .map_or(&self.bindings, |mode| &mode.bindings)
+    }
+
+    /// The trie over [`Keymap::bindings_for`] the same mode.
+    pub fn trie_for(&self, mode: Option<&str>) -> &Trie {
+        let key = mode
+            .filter(|name| self.modes.contains_key(*name))
+            .map(str::to_string);
+        &self.tries[&key]
+    }
+}
+
+pub fn default_bindings() -> Vec<Binding> {
+    DEFAULT_BINDINGS
+        .iter()
+        .map(|(chord, action)| {
+            Binding::parse(chord, action.clone())
+                .expect("default binding")
+                .repeating(DEFAULT_REPEATABLE.contains(action))
+        })
+        .collect()
+}
+
+/// Finds the first binding that conflicts with an earlier one, returning
+/// `(earlier, later)` indices. Besides exact duplicates this catches overlaps
+/// such as `ctrl+t` and `rctrl+t`, and sequences that start with another
+/// binding, such as `ctrl+space` and `ctrl+space, w`.
+pub fn find_conflict(bindings: &[Binding]) -> Option<(usize, usize)> {
+    bindings.iter().enumerate().find_map(|(later, binding)| {
+        bindings[..later]
+            .iter()
+            .position(|earlier| earlier.sequence.conflicts(&binding.sequence))
+            .map(|earlier| (earlier, later))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(sequences: &[&str]) -> Vec<Binding> {
+        sequences
+            .iter()
+            .map(|sequence| Binding::parse(sequence, Action::Center).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn default_bindings_do_not_conflict() {
+        assert_eq!(find_conflict(&default_bindings()), None);
+    }
+
+    #[test]
+    fn finds_overlapping_modifier_sides() {
+        assert_eq!(
+            find_conflict(&bindings(&["ctrl+t", "rctrl+t"])),
+            Some((0, 1))
+        );
+        assert_eq!(
+            find_conflict(&bindings(&["lctrl+t", "ctrl+t"])),
+            Some((0, 1))
+        );
+        assert_eq!(find_conflict(&bindings(&["lctrl+t", "rctrl+t"])), None);
+        assert_eq!(find_conflict(&bindings(&["ctrl+t", "ctrl+shift+t"])), None);
+    }
+
+    #[test]
+    fn finds_duplicates_and_prefixes() {
+        assert_eq!(
+            find_conflict(&bindings(&["a", "ctrl+t", "Shift+Ctrl+T", "shift+ctrl+t"])),
+            Some((2, 3))
+        );
+        assert_eq!(
+            find_conflict(&bindings(&["ctrl+space, w", "b", "ctrl+space"])),
+            Some((0, 2))
+        );
+        assert_eq!(
+            find_conflict(&bindings(&["ctrl+space, w", "ctrl+space, l"])),
+            None
+        );
+    }
+}
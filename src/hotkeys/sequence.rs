@@ -0,0 +1,248 @@
+use super::{Binding, Chord};
+use rdev::Key;
+use std::collections::HashSet;
+use std::fmt;
+
+/// One or more chords pressed one after another, e.g. `ctrl+space, w, l`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sequence(Vec<Chord>);
+
+impl Sequence {
+    /// Parses comma-separated chords; see [`Chord::parse`]. A comma right
+    /// after `+` or on its own is the comma key, so `cmd+,` and `ctrl+x, ,`
+    /// work.
+    pub fn parse(text: &str) -> Result<Sequence, String> {
+        let mut strokes = Vec::new();
+        let mut current = String::new();
+        for c in text.chars() {
+            let stroke = current.trim_end();
+            if c == ',' && !stroke.trim_start().is_empty() && !stroke.ends_with('+') {
+                strokes.push(std::mem::take(&mut current));
+            } else {
+                current.push(c);
+            }
+        }
+        strokes.push(current);
+
+        strokes
+            .iter()
+            .map(|stroke| match stroke.trim() {
+                "" => Err(format!("empty stroke in `{}`", text)),
+                stroke => Chord::parse(stroke),
+            })
+            .collect::<Result<_, _>>()
+            .map(Sequence)
+    }
+
+    pub fn chords(&self) -> &[Chord] {
+        &self.0
+    }
+
+    /// True when typing one sequence would also trigger the other or get
+    /// stuck on it: one is a prefix of the other, chord for chord
+    /// overlapping.
+    pub fn conflicts(&self, other: &Sequence) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| a.overlaps(b))
+    }
+}
+
+impl From<Chord> for Sequence {
+    fn from(chord: Chord) -> Self {
+        Sequence(vec![chord])
+    }
+}
+
+impl fmt::Display for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, chord) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", chord)?;
+        }
+        Ok(())
+    }
+}
+
+/// Bindings indexed by their sequences, one level per stroke.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trie {
+    children: Vec<(Chord, TrieNode)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrieNode {
+    /// Index of the binding the sequence ends at.
+    Binding(usize),
+    /// More strokes follow.
+    Prefix(Trie),
+}
+
+impl Trie {
+    /// Builds the trie for `bindings`, which must be free of conflicts; a
+    /// binding that would clash with an earlier one is left out.
+    pub fn new(bindings: &[Binding]) -> Trie {
+        let mut trie = Trie::default();
+        for (index, binding) in bindings.iter().enumerate() {
+            trie.insert(binding.sequence.chords(), index);
+        }
+        trie
+    }
+
+    fn insert(&mut self, chords: &[Chord], index: usize) {
+        let Some((&chord, rest)) = chords.split_first() else {
+            return;
+        };
+        let existing = self.children.iter_mut().find(|(c, _)| *c == chord);
+        match (existing, rest.is_empty()) {
+            (None, true) => self.children.push((chord, TrieNode::Binding(index))),
+            (None, false) => {
+                let mut next = Trie::default();
+                next.insert(rest, index);
+                self.children.push((chord, TrieNode::Prefix(next)));
+            }
+            (Some((_, TrieNode::Prefix(next))), false) => next.insert(rest, index),
+            (Some(_), _) => {}
+        }
+    }
+
+    /// The node reached by pressing `key` with `held` down, and the chord
+    /// that matched.
+    pub fn step(&self, key: Key, held: &HashSet<Key>) -> Option<(Chord, &TrieNode)> {
+        self.children
+            .iter()
+            .find(|(chord, _)| chord.key == key && chord.is_pressed(held))
+            .map(|(chord, node)| (*chord, node))
+    }
+
+    /// Follows chords that were matched by earlier [`Trie::step`] calls.
+    pub fn walk(&self, chords: &[Chord]) -> Option<&TrieNode> {
+        let (first, rest) = chords.split_first()?;
+        let (_, node) = self.children.iter().find(|(c, _)| c == first)?;
+        match (node, rest.is_empty()) {
+            (_, true) => Some(node),
+            (TrieNode::Prefix(next), false) => next.walk(rest),
+            (TrieNode::Binding(_), false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Action;
+
+    fn chord(text: &str) -> Chord {
+        Chord::parse(text).unwrap()
+    }
+
+    fn held(keys: &[Key]) -> HashSet<Key> {
+        keys.iter().copied().collect()
+    }
+
+    #[test]
+    fn parse_splits_on_commas() {
+        let sequence = Sequence::parse("ctrl+space, w,l").unwrap();
+        assert_eq!(
+            sequence.chords(),
+            [chord("ctrl+space"), chord("w"), chord("l")]
+        );
+        assert_eq!(sequence.to_string(), "ctrl+space, w, l");
+    }
+
+    #[test]
+    fn parse_reads_a_comma_after_plus_or_alone_as_the_key() {
+        assert_eq!(
+            Sequence::parse("cmd+,").unwrap().chords(),
+            [chord("cmd+comma")]
+        );
+        assert_eq!(
+            Sequence::parse("ctrl+x, ,").unwrap().chords(),
+            [chord("ctrl+x"), chord("comma")]
+        );
+        assert_eq!(
+            Sequence::parse("cmd+, , x").unwrap().chords(),
+            [chord("cmd+comma"), chord("x")]
+        );
+        assert_eq!(Sequence::parse(",").unwrap().chords(), [chord("comma")]);
+    }
+
+    #[test]
+    fn parse_rejects_empty_strokes() {
+        assert_eq!(
+            Sequence::parse("ctrl+x,"),
+            Err("empty stroke in `ctrl+x,`".to_string())
+        );
+        assert_eq!(
+            Sequence::parse("ctrl+x, "),
+            Err("empty stroke in `ctrl+x, `".to_string())
+        );
+        assert_eq!(Sequence::parse(""), Err("empty stroke in ``".to_string()));
+    }
+
+    #[test]
+    fn conflicts_when_one_starts_the_other() {
+        let parse = |text| Sequence::parse(text).unwrap();
+        assert!(parse("ctrl+space").conflicts(&parse("ctrl+space, w")));
+        assert!(parse("ctrl+space, w").conflicts(&parse("rctrl+space, w, l")));
+        assert!(!parse("ctrl+space, w").conflicts(&parse("ctrl+space, l")));
+        assert!(!parse("ctrl+space").conflicts(&parse("alt+space, w")));
+    }
+
+    fn trie() -> Trie {
+        let bindings: Vec<Binding> = [
+            ("ctrl+space, w, l", Action::TileLeft),
+            ("ctrl+space, w, r", Action::TileRight),
+            ("ctrl+space, a", Action::AutoArrange),
+            ("x", Action::Center),
+        ]
+        .into_iter()
+        .map(|(sequence, action)| Binding::parse(sequence, action).unwrap())
+        .collect();
+        Trie::new(&bindings)
+    }
+
+    #[test]
+    fn step_matches_the_chord_held_down() {
+        let trie = trie();
+
+        let (matched, node) = trie
+            .step(Key::Space, &held(&[Key::ControlRight, Key::Space]))
+            .unwrap();
+        assert_eq!(matched, chord("ctrl+space"));
+        assert!(matches!(node, TrieNode::Prefix(_)));
+
+        assert_eq!(
+            trie.step(Key::KeyX, &held(&[Key::KeyX])),
+            Some((chord("x"), &TrieNode::Binding(3)))
+        );
+        // Exactly the chord's modifiers must be held
+        assert_eq!(trie.step(Key::Space, &held(&[Key::Space])), None);
+        assert_eq!(
+            trie.step(Key::KeyX, &held(&[Key::ShiftLeft, Key::KeyX])),
+            None
+        );
+        // Later strokes are not reachable from the root
+        assert_eq!(trie.step(Key::KeyW, &held(&[Key::KeyW])), None);
+    }
+
+    #[test]
+    fn walk_follows_matched_chords() {
+        let trie = trie();
+        let (space, w, r, a) = (chord("ctrl+space"), chord("w"), chord("r"), chord("a"));
+
+        let Some(TrieNode::Prefix(after_w)) = trie.walk(&[space, w]) else {
+            panic!("`ctrl+space, w` should be a prefix");
+        };
+        assert_eq!(
+            after_w.step(Key::KeyR, &held(&[Key::KeyR])),
+            Some((r, &TrieNode::Binding(1)))
+        );
+        assert_eq!(trie.walk(&[space, w, r]), Some(&TrieNode::Binding(1)));
+        assert_eq!(trie.walk(&[space, a]), Some(&TrieNode::Binding(2)));
+
+        assert_eq!(trie.walk(&[]), None);
+        assert_eq!(trie.walk(&[w]), None);
+        assert_eq!(trie.walk(&[space, a, w]), None);
+    }
+}
@@ -0,0 +1,197 @@
+use super::Modifier;
+use rdev::{EventType, Key};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// How long a held key may go without an event before it is assumed to have
+/// been released. Non-modifier keys auto-repeat while held, so silence means
+/// a release was missed. Modifiers do not repeat; they get longer, and a key
+/// auto-repeating while they are held counts as a sign of them.
+const KEY_STALE_AFTER: Duration = Duration::from_secs(2);
+const MODIFIER_STALE_AFTER: Duration = Duration::from_secs(10);
+
+/// A non-modifier key going down, with every key held at that moment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stroke {
+    pub key: Key,
+    pub held: HashSet<Key>,
+    /// OS auto-repeat of a key that is still held.
+    pub repeat: bool,
+}
+
+/// Turns raw key events into strokes.
+///
+/// Modifiers only change what is held; a stroke is reported when another
+/// key goes down. Auto-repeat is reported as a stroke marked `repeat`, so
+/// callers can fire a chord once per press and let only bindings marked
+/// `repeat` fire again while it is held.
+///
+/// Release events can be lost, e.g. while the screen is locked. A press of a
+/// key believed to be down is only a repeat if the key was seen recently,
+/// and keys silent for too long are dropped, so a missed release never
+/// disables a binding for good.
+#[derive(Debug, Clone, Default)]
+pub struct ChordTracker {
+    /// Held keys and when each was last reported.
+    held: HashMap<Key, Instant>,
+}
+
+impl ChordTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn held_keys(&self) -> HashSet<Key> {
+        self.held.keys().copied().collect()
+    }
+
+    /// Forgets all held keys, for when events are known to have been missed.
+    pub fn reset(&mut self) {
+        self.held.clear();
+    }
+
+    /// Records `event`, received at `now`, and returns the stroke it makes.
+    pub fn handle(&mut self, event: &EventType, now: Instant) -> Option<Stroke> {
+        self.held.retain(|&key, &mut seen| {
+            let stale_after = if Modifier::is_modifier_key(key) {
+                MODIFIER_STALE_AFTER
+            } else {
+                KEY_STALE_AFTER
+            };
+            now.saturating_duration_since(seen) <= stale_after
+        });
+
+        match *event {
+            EventType::KeyPress(key) => {
+                let repeat = self.held.insert(key, now).is_some();
+                if repeat && !Modifier::is_modifier_key(key) {
+                    // A key auto-repeating under held modifiers, e.g. a
+                    // repeating binding being held, shows they are still down
+                    for (&key, seen) in &mut self.held {
+                        if Modifier::is_modifier_key(key) {
+                            *seen = now;
+                        }
+                    }
+                }
+                (!Modifier::is_modifier_key(key)).then(|| Stroke {
+                    key,
+                    held: self.held_keys(),
+                    repeat,
+                })
+            }
+            EventType::KeyRelease(key) => {
+                self.held.remove(&key);
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(keys: &[Key]) -> HashSet<Key> {
+        keys.iter().copied().collect()
+    }
+
+    #[test]
+    fn modifiers_only_change_what_is_held() {
+        let mut tracker = ChordTracker::new();
+        let now = Instant::now();
+
+        assert_eq!(
+            tracker.handle(&EventType::KeyPress(Key::ControlLeft), now),
+            None
+        );
+        assert_eq!(
+            tracker.handle(&EventType::KeyPress(Key::KeyT), now),
+            Some(Stroke {
+                key: Key::KeyT,
+                held: held(&[Key::ControlLeft, Key::KeyT]),
+                repeat: false,
+            })
+        );
+        assert!(
+            tracker
+                .handle(&EventType::KeyPress(Key::KeyT), now)
+                .unwrap()
+                .repeat
+        );
+
+        tracker.handle(&EventType::KeyRelease(Key::KeyT), now);
+        tracker.handle(&EventType::KeyRelease(Key::ControlLeft), now);
+        assert!(tracker.held_keys().is_empty());
+    }
+
+    #[test]
+    fn modifiers_stay_held_while_a_key_repeats() {
+        let mut tracker = ChordTracker::new();
+        let start = Instant::now();
+        tracker.handle(&EventType::KeyPress(Key::ControlLeft), start);
+        tracker.handle(&EventType::KeyPress(Key::ShiftLeft), start);
+
+        for second in 0..30 {
+            let now = start + Duration::from_secs(second);
+            let stroke = tracker
+                .handle(&EventType::KeyPress(Key::KeyL), now)
+                .unwrap();
+            assert_eq!(
+                stroke.held,
+                held(&[Key::ControlLeft, Key::ShiftLeft, Key::KeyL])
+            );
+            assert_eq!(stroke.repeat, second > 0);
+        }
+    }
+
+    #[test]
+    fn typing_does_not_keep_a_released_modifier_alive() {
+        let mut tracker = ChordTracker::new();
+        let start = Instant::now();
+        tracker.handle(&EventType::KeyPress(Key::ControlLeft), start);
+        // The release of ctrl is lost, then the user keeps typing
+        for step in 0..120 {
+            let now = start + Duration::from_millis(500 * step);
+            tracker.handle(&EventType::KeyPress(Key::KeyE), now);
+            tracker.handle(&EventType::KeyRelease(Key::KeyE), now);
+        }
+
+        let now = start + Duration::from_secs(60);
+        tracker.handle(&EventType::KeyPress(Key::ShiftLeft), now);
+        let stroke = tracker
+            .handle(&EventType::KeyPress(Key::KeyT), now)
+            .unwrap();
+        assert_eq!(stroke.held, held(&[Key::ShiftLeft, Key::KeyT]));
+    }
+
+    #[test]
+    fn missed_releases_expire() {
+        let mut tracker = ChordTracker::new();
+        let start = Instant::now();
+        tracker.handle(&EventType::KeyPress(Key::ControlLeft), start);
+        tracker.handle(&EventType::KeyPress(Key::KeyT), start);
+
+        // The key has stopped repeating, so it was released
+        let later = start + KEY_STALE_AFTER + Duration::from_secs(1);
+        let stroke = tracker
+            .handle(&EventType::KeyPress(Key::KeyT), later)
+            .unwrap();
+        assert!(!stroke.repeat);
+        assert_eq!(stroke.held, held(&[Key::ControlLeft, Key::KeyT]));
+
+        // Mouse events are no sign of a held modifier
+        let much_later = later + MODIFIER_STALE_AFTER + Duration::from_secs(1);
+        tracker.handle(
+            &EventType::MouseMove { x: 0.0, y: 0.0 },
+            later + Duration::from_secs(5),
+        );
+        assert_eq!(
+            tracker
+                .handle(&EventType::KeyPress(Key::KeyT), much_later)
+                .unwrap()
+                .held,
+            held(&[Key::KeyT])
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use super::clamp_ratio;
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+
+/// How a split node divides its area between its two children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitAxis {
+    /// First child on the left, second on the right.
+    LeftRight,
+    /// First child on top, second below.
+    TopBottom,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Leaf(WindowId),
+    Split {
+        axis: SplitAxis,
+        /// Fraction of the area given to the first child.
+        ratio: f64,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+}
+
+impl Node {
+    fn split(axis: SplitAxis, first: Node, second: Node) -> Node {
+        Node::Split {
+            axis,
+            ratio: 0.5,
+            first: Box::new(first),
+            second: Box::new(second),
+        }
+    }
+
+    fn contains(&self, window: WindowId) -> bool {
+        match self {
+            Node::Leaf(id) => *id == window,
+            Node::Split { first, second, .. } => first.contains(window) || second.contains(window),
+        }
+    }
+
+    fn leaves(&self, out: &mut Vec<WindowId>) {
+        match self {
+            Node::Leaf(id) => out.push(*id),
+            Node::Split { first, second, .. } => {
+                first.leaves(out);
+                second.leaves(out);
+            }
+        }
+    }
+
+    fn last_leaf(&self) -> WindowId {
+        match self {
+            Node::Leaf(id) => *id,
+            Node::Split { second, .. } => second.last_leaf(),
+        }
+    }
+
+    fn frames(&self, area: Rect, out: &mut Vec<(WindowId, Rect)>) {
+        match self {
+            Node::Leaf(id) => out.push((*id, area)),
+            Node::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = split_area(area, *axis, *ratio);
+                first.frames(first_area, out);
+                second.frames(second_area, out);
+            }
+        }
+    }
+
+    fn leaf_area(&self, window: WindowId, area: Rect) -> Option<Rect> {
+        match self {
+            Node::Leaf(id) => (*id == window).then_some(area),
+            Node::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, second_area) = split_area(area, *axis, *ratio);
+                first
+                    .leaf_area(window, first_area)
+                    .or_else(|| second.leaf_area(window, second_area))
+            }
+        }
+    }
+
+    fn leaf_mut(&mut self, window: WindowId) -> Option<&mut Node> {
+        if *self == Node::Leaf(window) {
+            return Some(self);
+        }
+        match self {
+            Node::Leaf(_) => None,
+            Node::Split { first, second, .. } => match first.leaf_mut(window) {
+                Some(leaf) => Some(leaf),
+                None => second.leaf_mut(window),
+            },
+        }
+    }
+
+    /// Returns the tree with `window` removed, collapsing its parent split
+    /// into the sibling.
+    fn without(self, window: WindowId) -> Option<Node> {
+        match self {
+            Node::Leaf(id) if id == window => None,
+            Node::Leaf(_) => Some(self),
+            Node::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => match (first.without(window), second.without(window)) {
+                (Some(first), Some(second)) => Some(Node::Split {
+                    axis,
+                    ratio,
+                    first: Box::new(first),
+                    second: Box::new(second),
+                }),
+                (Some(only), None) | (None, Some(only)) => Some(only),
+                (None, None) => None,
+            },
+        }
+    }
+
+    /// Adjusts the split directly above `window` so the window grows by
+    /// `delta`. Returns false if the window is not a child of any split.
+    fn resize(&mut self, window: WindowId, delta: f64) -> bool {
+        let Node::Split {
+            ratio,
+            first,
+            second,
+            ..
+        } = self
+        else {
+            return false;
+        };
+
+        if **first == Node::Leaf(window) {
+            *ratio = clamp_ratio(*ratio + delta);
+            true
+        } else if **second == Node::Leaf(window) {
+            *ratio = clamp_ratio(*ratio - delta);
+            true
+        } else {
+            first.resize(window, delta) || second.resize(window, delta)
+        }
+    }
+
+    /// Sets every split's ratio to its first child's share of the leaves
+    /// below it, so all windows get the same area. Returns the leaf count.
+    fn balance(&mut self) -> usize {
+        match self {
+            Node::Leaf(_) => 1,
+            Node::Split {
+                ratio,
+                first,
+                second,
+                ..
+            } => {
+                let (first, second) = (first.balance(), second.balance());
+                *ratio = first as f64 / (first + second) as f64;
+                first + second
+            }
+        }
+    }
+
+    fn for_each_split(&mut self, f: &mut impl FnMut(&mut SplitAxis, &mut f64, &mut bool)) {
+        if let Node::Split {
+            axis,
+            ratio,
+            first,
+            second,
+        } = self
+        {
+            let mut swap = false;
+            f(axis, ratio, &mut swap);
+            if swap {
+                std::mem::swap(first, second);
+            }
+            first.for_each_split(f);
+            second.for_each_split(f);
+        }
+    }
+}
+
+fn split_area(area: Rect, axis: SplitAxis, ratio: f64) -> (Rect, Rect) {
+    match axis {
+        SplitAxis::LeftRight => area.split_left_right(ratio),
+        SplitAxis::TopBottom => area.split_top_bottom(ratio),
+    }
+}
+
+/// A binary space partitioning tree of windows, one per display.
+///
+/// Each new window splits the focused leaf in half along the leaf's longer
+/// side. The tree only stores structure and ratios; frames are computed for
+/// whatever screen rectangle is passed to [`BspTree::arrange`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BspTree {
+    root: Option<Node>,
+}
+
+impl BspTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn contains(&self, window: WindowId) -> bool {
+        self.root.as_ref().is_some_and(|root| root.contains(window))
+    }
+
+    /// Windows in tree order (first children before second children).
+    pub fn windows(&self) -> Vec<WindowId> {
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            root.leaves(&mut out);
+        }
+        out
+    }
+
+    /// Inserts `window` by splitting the `focused` leaf, or the last leaf
+    /// when the focused window is not part of the tree.
+    pub fn insert(&mut self, window: WindowId, focused: Option<WindowId>, screen: Rect) {
+        if self.contains(window) {
+            return;
+        }
+
+        let Some(root) = &mut self.root else {
+            self.root = Some(Node::Leaf(window));
+            return;
+        };
+
+        let target = focused
+            .filter(|&id| root.contains(id))
+            .unwrap_or_else(|| root.last_leaf());
+        let Some(area) = root.leaf_area(target, screen) else {
+            return;
+        };
+        let axis = if area.width >= area.height {
+            SplitAxis::LeftRight
+        } else {
+            SplitAxis::TopBottom
+        };
+
+        if let Some(leaf) = root.leaf_mut(target) {
+            *leaf = Node::split(axis, Node::Leaf(target), Node::Leaf(window));
+        }
+    }
+
+    /// Removes `window`; its sibling takes over the parent's area.
+    pub fn remove(&mut self, window: WindowId) {
+        self.root = self.root.take().and_then(|root| root.without(window));
+    }
+
+    /// Brings the tree in line with the current window list: closed windows
+    /// are removed and new ones inserted in list order.
+    pub fn sync(&mut self, windows: &[WindowId], focused: Option<WindowId>, screen: Rect) {
+        for stale in self.windows() {
+            if !windows.contains(&stale) {
+                self.remove(stale);
+            }
+        }
+        for &window in windows {
+            self.insert(window, focused, screen);
+        }
+    }
+
+    pub fn arrange(&self, screen: Rect) -> Vec<(WindowId, Rect)> {
+        let mut frames = Vec::new();
+        if let Some(root) = &self.root {
+            root.frames(screen, &mut frames);
+        }
+        frames
+    }
+
+    /// Grows (positive `delta`) or shrinks `window` by moving the split it
+    /// shares with its sibling.
+    pub fn resize(&mut self, window: WindowId, delta: f64) -> bool {
+        self.root
+            .as_mut()
+            .is_some_and(|root| root.resize(window, delta))
+    }
+
+    /// Rotates the whole tree 90 degrees clockwise.
+    pub fn rotate(&mut self) {
+        self.for_each_split(|axis, ratio, swap| match axis {
+            SplitAxis::LeftRight => *axis = SplitAxis::TopBottom,
+            SplitAxis::TopBottom => {
+                // The top child ends up on the right
+                *axis = SplitAxis::LeftRight;
+                *ratio = 1.0 - *ratio;
+                *swap = true;
+            }
+        });
+    }
+
+    /// Mirrors the tree across `axis`: `LeftRight` swaps left and right
+    /// children, `TopBottom` swaps top and bottom children.
+    pub fn flip(&mut self, mirror: SplitAxis) {
+        self.for_each_split(|axis, ratio, swap| {
+            if *axis == mirror {
+                *ratio = 1.0 - *ratio;
+                *swap = true;
+            }
+        });
+    }
+
+    /// Divides each split in proportion to the windows on either side, so
+    /// every window gets the same share of the screen.
+    pub fn balance(&mut self) {
+        if let Some(root) = &mut self.root {
+            root.balance();
+        }
+    }
+
+    fn for_each_split(&mut self, mut f: impl FnMut(&mut SplitAxis, &mut f64, &mut bool)) {
+        if let Some(root) = &mut self.root {
+            root.for_each_split(&mut f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1000.0,
+        height: 600.0,
+    };
+
+    /// Builds a tree by inserting each window with the one before it
+    /// focused.
+    fn tree(windows: &[u32]) -> BspTree {
+        let mut tree = BspTree::new();
+        let mut focused = None;
+        for &window in windows {
+            tree.insert(WindowId(window), focused, SCREEN);
+            focused = Some(WindowId(window));
+        }
+        tree
+    }
+
+    fn frame(window: u32, x: f64, y: f64, width: f64, height: f64) -> (WindowId, Rect) {
+        (WindowId(window), Rect::new(x, y, width, height))
+    }
+
+    #[test]
+    fn first_window_takes_the_screen() {
+        assert_eq!(
+            tree(&[1]).arrange(SCREEN),
+            vec![frame(1, 0.0, 0.0, 1000.0, 600.0)]
+        );
+        assert!(tree(&[]).arrange(SCREEN).is_empty());
+    }
+
+    #[test]
+    fn insert_splits_focused_leaf_along_its_longer_side() {
+        assert_eq!(
+            tree(&[1, 2, 3]).arrange(SCREEN),
+            vec![
+                frame(1, 0.0, 0.0, 500.0, 600.0),
+                frame(2, 500.0, 0.0, 500.0, 300.0),
+                frame(3, 500.0, 300.0, 500.0, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_splits_last_leaf_without_focus() {
+        let mut tree = tree(&[1, 2]);
+        tree.insert(WindowId(3), Some(WindowId(9)), SCREEN);
+        tree.insert(WindowId(3), Some(WindowId(1)), SCREEN);
+
+        assert_eq!(tree.windows(), vec![WindowId(1), WindowId(2), WindowId(3)]);
+        assert_eq!(
+            tree.arrange(SCREEN)[2],
+            frame(3, 500.0, 300.0, 500.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn remove_collapses_parent_into_sibling() {
+        let mut tree = tree(&[1, 2, 3]);
+
+        tree.remove(WindowId(2));
+
+        assert_eq!(
+            tree.arrange(SCREEN),
+            vec![
+                frame(1, 0.0, 0.0, 500.0, 600.0),
+                frame(3, 500.0, 0.0, 500.0, 600.0),
+            ]
+        );
+
+        tree.remove(WindowId(1));
+        tree.remove(WindowId(3));
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn sync_drops_closed_windows_and_adds_new_ones() {
+        let mut tree = tree(&[1, 2, 3]);
+
+        tree.sync(
+            &[WindowId(3), WindowId(1), WindowId(4)],
+            Some(WindowId(1)),
+            SCREEN,
+        );
+
+        assert_eq!(
+            tree.arrange(SCREEN),
+            vec![
+                frame(1, 0.0, 0.0, 500.0, 300.0),
+                frame(4, 0.0, 300.0, 500.0, 300.0),
+                frame(3, 500.0, 0.0, 500.0, 600.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn rotate_turns_the_tree_clockwise() {
+        let mut tree = tree(&[1, 2, 3]);
+
+        tree.rotate();
+
+        assert_eq!(
+            tree.arrange(SCREEN),
+            vec![
+                frame(1, 0.0, 0.0, 1000.0, 300.0),
+                frame(3, 0.0, 300.0, 500.0, 300.0),
+                frame(2, 500.0, 300.0, 500.0, 300.0),
+            ]
+        );
+
+        for _ in 0..3 {
+            tree.rotate();
+        }
+        assert_eq!(tree, self::tree(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn flip_mirrors_splits_along_one_axis() {
+        let mut left_right = tree(&[1, 2, 3]);
+        left_right.flip(SplitAxis::LeftRight);
+
+        assert_eq!(
+            left_right.arrange(SCREEN),
+            vec![
+                frame(2, 0.0, 0.0, 500.0, 300.0),
+                frame(3, 0.0, 300.0, 500.0, 300.0),
+                frame(1, 500.0, 0.0, 500.0, 600.0),
+            ]
+        );
+
+        let mut top_bottom = tree(&[1, 2, 3]);
+        top_bottom.flip(SplitAxis::TopBottom);
+
+        assert_eq!(
+            top_bottom.arrange(SCREEN),
+            vec![
+                frame(1, 0.0, 0.0, 500.0, 600.0),
+                frame(3, 500.0, 0.0, 500.0, 300.0),
+                frame(2, 500.0, 300.0, 500.0, 300.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn resize_moves_the_split_next_to_the_window() {
+        let mut tree = tree(&[1, 2]);
+
+        assert!(tree.resize(WindowId(1), 0.1));
+        assert_eq!(tree.arrange(SCREEN)[0], frame(1, 0.0, 0.0, 600.0, 600.0));
+        assert!(tree.resize(WindowId(2), 1.0));
+        assert_eq!(tree.arrange(SCREEN)[0], frame(1, 0.0, 0.0, 100.0, 600.0));
+
+        assert!(!tree.resize(WindowId(3), 0.1));
+        assert!(!self::tree(&[1]).resize(WindowId(1), 0.1));
+    }
+
+    #[test]
+    fn balance_gives_every_window_the_same_area() {
+        // One window on the left, three nested on the right
+        let mut tree = tree(&[1, 2, 3, 4]);
+        tree.resize(WindowId(1), 0.2);
+
+        tree.balance();
+
+        for (window, frame) in tree.arrange(SCREEN) {
+            let area = frame.width * frame.height;
+            assert!(
+                (area - 150_000.0).abs() < 1e-6,
+                "{:?} has area {}",
+                window,
+                area
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+use serde::Deserialize;
+
+/// Where the remaining space goes after each split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DwindleStyle {
+    /// Every window takes the left or top half, so windows shrink towards the
+    /// bottom-right corner.
+    #[default]
+    Dwindle,
+    /// Windows take the left, top, right and bottom half in turn, so they
+    /// spiral inwards.
+    Spiral,
+}
+
+/// Each window takes half of the space left over by the previous ones,
+/// alternating between side-by-side and stacked splits. The first split runs
+/// across the longer side of the screen; the last window takes whatever is
+/// left.
+pub fn dwindle(screen: Rect, windows: &[WindowId], style: DwindleStyle) -> Vec<(WindowId, Rect)> {
+    let mut frames = Vec::with_capacity(windows.len());
+    let mut remaining = screen;
+    let side_by_side_first = screen.width >= screen.height;
+
+    for (i, &window) in windows.iter().enumerate() {
+        if i == windows.len() - 1 {
+            frames.push((window, remaining));
+            break;
+        }
+
+        let side_by_side = (i % 2 == 0) == side_by_side_first;
+        // The spiral reverses direction on every second pair of splits
+        let reversed = style == DwindleStyle::Spiral && i % 4 >= 2;
+
+        let (first, second) = if side_by_side {
+            remaining.split_left_right(0.5)
+        } else {
+            remaining.split_top_bottom(0.5)
+        };
+        let (tile, rest) = if reversed {
+            (second, first)
+        } else {
+            (first, second)
+        };
+
+        frames.push((window, tile));
+        remaining = rest;
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::assert_tiles_cover;
+
+    fn windows(count: u32) -> Vec<WindowId> {
+        (0..count).map(WindowId).collect()
+    }
+
+    #[test]
+    fn covers_screen_for_any_window_count() {
+        for screen in [
+            Rect::new(0.0, 0.0, 1440.0, 900.0),
+            Rect::new(100.0, 50.0, 800.0, 1280.0),
+        ] {
+            for count in 0..16 {
+                for style in [DwindleStyle::Dwindle, DwindleStyle::Spiral] {
+                    let windows = windows(count);
+                    assert_tiles_cover(screen, &windows, &dwindle(screen, &windows, style));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dwindle_shrinks_towards_bottom_right() {
+        let screen = Rect::new(0.0, 0.0, 1600.0, 800.0);
+
+        let tiles = dwindle(screen, &windows(4), DwindleStyle::Dwindle);
+
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 800.0, 800.0)),
+                (WindowId(1), Rect::new(800.0, 0.0, 800.0, 400.0)),
+                (WindowId(2), Rect::new(800.0, 400.0, 400.0, 400.0)),
+                (WindowId(3), Rect::new(1200.0, 400.0, 400.0, 400.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn spiral_turns_inwards() {
+        let screen = Rect::new(0.0, 0.0, 1600.0, 800.0);
+
+        let tiles = dwindle(screen, &windows(5), DwindleStyle::Spiral);
+
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 800.0, 800.0)),
+                (WindowId(1), Rect::new(800.0, 0.0, 800.0, 400.0)),
+                (WindowId(2), Rect::new(1200.0, 400.0, 400.0, 400.0)),
+                (WindowId(3), Rect::new(800.0, 600.0, 400.0, 200.0)),
+                (WindowId(4), Rect::new(800.0, 400.0, 400.0, 200.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn first_split_runs_across_longer_side() {
+        let screen = Rect::new(0.0, 0.0, 800.0, 1600.0);
+
+        let tiles = dwindle(screen, &windows(2), DwindleStyle::Dwindle);
+
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 800.0, 800.0)),
+                (WindowId(1), Rect::new(0.0, 800.0, 800.0, 800.0)),
+            ]
+        );
+    }
+}
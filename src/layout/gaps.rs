@@ -0,0 +1,144 @@
+use crate::backend::WindowId;
+use crate::geometry::{Insets, Rect};
+use serde::Deserialize;
+
+// Layout arithmetic is floating point, so edges that should line up may be
+// off by a rounding error.
+const EDGE_TOLERANCE: f64 = 0.5;
+
+/// Spacing applied around and between tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gaps {
+    /// Space between two neighbouring tiles.
+    pub inner: f64,
+    /// Space between tiles and the screen border.
+    pub outer: f64,
+    /// Extra space reserved at each edge, e.g. for a status bar.
+    pub padding: Insets,
+}
+
+impl Gaps {
+    /// The area layouts tile into: the screen minus padding and outer gaps.
+    pub fn tiling_area(&self, screen: Rect) -> Rect {
+        screen
+            .inset(self.padding)
+            .inset(Insets::uniform(self.outer))
+    }
+
+    /// Computes `layout` for the tiling area of `screen` and separates the
+    /// resulting tiles by the inner gap.
+    pub fn apply(
+        &self,
+        screen: Rect,
+        layout: impl FnOnce(Rect) -> Vec<(WindowId, Rect)>,
+    ) -> Vec<(WindowId, Rect)> {
+        let area = self.tiling_area(screen);
+        layout(area)
+            .into_iter()
+            .map(|(window, tile)| (window, self.separate(area, tile)))
+            .collect()
+    }
+
+    /// Pulls every edge of `tile` that faces into `area`, rather than lying
+    /// on its border, in by half the inner gap. Two tiles sharing an edge end
+    /// up a full inner gap apart, whatever layout produced them.
+    pub fn separate(&self, area: Rect, tile: Rect) -> Rect {
+        let half = self.inner / 2.0;
+        let interior = |edge: f64, border: f64| {
+            if (edge - border).abs() > EDGE_TOLERANCE {
+                half
+            } else {
+                0.0
+            }
+        };
+        tile.inset(Insets {
+            top: interior(tile.y, area.y),
+            right: interior(tile.right(), area.right()),
+            bottom: interior(tile.bottom(), area.bottom()),
+            left: interior(tile.x, area.x),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1000.0,
+        height: 600.0,
+    };
+
+    fn gaps(inner: f64, outer: f64) -> Gaps {
+        Gaps {
+            inner,
+            outer,
+            padding: Insets::default(),
+        }
+    }
+
+    #[test]
+    fn separate_leaves_border_edges_alone() {
+        assert_eq!(gaps(10.0, 0.0).separate(AREA, AREA), AREA);
+    }
+
+    #[test]
+    fn separate_pulls_in_interior_edges() {
+        let gaps = gaps(10.0, 0.0);
+        let (left, right) = AREA.split_left_right(0.5);
+        let (top_right, bottom_right) = right.split_top_bottom(0.5);
+
+        assert_eq!(gaps.separate(AREA, left), Rect::new(0.0, 0.0, 495.0, 600.0));
+        assert_eq!(
+            gaps.separate(AREA, top_right),
+            Rect::new(505.0, 0.0, 495.0, 295.0)
+        );
+        assert_eq!(
+            gaps.separate(AREA, bottom_right),
+            Rect::new(505.0, 305.0, 495.0, 295.0)
+        );
+    }
+
+    #[test]
+    fn separate_treats_rounding_errors_as_on_the_border() {
+        let tile = Rect::new(0.2, 0.0, 499.7, 600.0);
+
+        assert_eq!(
+            gaps(10.0, 0.0).separate(AREA, tile),
+            Rect::new(0.2, 0.0, 494.7, 600.0)
+        );
+    }
+
+    #[test]
+    fn tiling_area_removes_padding_and_outer_gap() {
+        let gaps = Gaps {
+            padding: Insets {
+                top: 30.0,
+                ..Insets::default()
+            },
+            ..gaps(10.0, 20.0)
+        };
+
+        assert_eq!(gaps.tiling_area(AREA), Rect::new(20.0, 50.0, 960.0, 530.0));
+    }
+
+    #[test]
+    fn apply_separates_every_tile() {
+        let gaps = gaps(10.0, 20.0);
+        let tiles = gaps.apply(AREA, |area| {
+            let (left, right) = area.split_left_right(0.5);
+            vec![(WindowId(1), left), (WindowId(2), right)]
+        });
+
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(1), Rect::new(20.0, 20.0, 475.0, 560.0)),
+                (WindowId(2), Rect::new(505.0, 20.0, 475.0, 560.0)),
+            ]
+        );
+    }
+}
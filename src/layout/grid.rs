@@ -0,0 +1,84 @@
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+
+/// Columns and rows of the grid used for `count` windows. Columns are
+/// filled first, so the last row may be partially empty.
+pub fn grid_dimensions(count: usize) -> (usize, usize) {
+    if count == 0 {
+        return (0, 0);
+    }
+    let cols = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(cols);
+    (cols, rows)
+}
+
+/// Lays windows out row by row in equally sized cells.
+pub fn grid(screen: Rect, windows: &[WindowId]) -> Vec<(WindowId, Rect)> {
+    let (cols, rows) = grid_dimensions(windows.len());
+    if cols == 0 {
+        return Vec::new();
+    }
+
+    let tile_width = screen.width / cols as f64;
+    let tile_height = screen.height / rows as f64;
+
+    windows
+        .iter()
+        .enumerate()
+        .map(|(i, &window)| {
+            let col = i % cols;
+            let row = i / cols;
+            let x = screen.x + col as f64 * tile_width;
+            let y = screen.y + row as f64 * tile_height;
+            (window, Rect::new(x, y, tile_width, tile_height))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::assert_tiles_cover;
+
+    #[test]
+    fn dimensions_fill_columns_first() {
+        assert_eq!(grid_dimensions(0), (0, 0));
+        assert_eq!(grid_dimensions(1), (1, 1));
+        assert_eq!(grid_dimensions(2), (2, 1));
+        assert_eq!(grid_dimensions(3), (2, 2));
+        assert_eq!(grid_dimensions(4), (2, 2));
+        assert_eq!(grid_dimensions(5), (3, 2));
+        assert_eq!(grid_dimensions(9), (3, 3));
+        assert_eq!(grid_dimensions(10), (4, 3));
+    }
+
+    #[test]
+    fn lays_out_row_by_row() {
+        let screen = Rect::new(10.0, 20.0, 900.0, 600.0);
+        let windows: Vec<WindowId> = (0..5).map(WindowId).collect();
+
+        let tiles = grid(screen, &windows);
+
+        let cell =
+            |col: f64, row: f64| Rect::new(10.0 + col * 300.0, 20.0 + row * 300.0, 300.0, 300.0);
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(0), cell(0.0, 0.0)),
+                (WindowId(1), cell(1.0, 0.0)),
+                (WindowId(2), cell(2.0, 0.0)),
+                (WindowId(3), cell(0.0, 1.0)),
+                (WindowId(4), cell(1.0, 1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn full_grids_cover_screen() {
+        let screen = Rect::new(0.0, 0.0, 1440.0, 900.0);
+        for count in [0, 1, 2, 4, 6, 9, 12, 16] {
+            let windows: Vec<WindowId> = (0..count).map(WindowId).collect();
+            assert_tiles_cover(screen, &windows, &grid(screen, &windows));
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::clamp_ratio;
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+use serde::Deserialize;
+
+/// Which side of the screen the stack occupies; the master area takes the
+/// opposite side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StackSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// dwm-style layout: the first `master_count` windows share the master area,
+/// the remaining windows are stacked next to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MasterStack {
+    /// Fraction of the screen given to the master area when there is a stack.
+    pub ratio: f64,
+    pub master_count: usize,
+    pub stack_side: StackSide,
+}
+
+impl Default for MasterStack {
+    fn default() -> Self {
+        MasterStack {
+            ratio: 0.5,
+            master_count: 1,
+            stack_side: StackSide::Right,
+        }
+    }
+}
+
+impl MasterStack {
+    pub fn arrange(&self, screen: Rect, windows: &[WindowId]) -> Vec<(WindowId, Rect)> {
+        let master_count = self.master_count.min(windows.len());
+        let (masters, stack) = windows.split_at(master_count);
+
+        // Whichever side is populated gets the whole screen on its own
+        let (master_area, stack_area) = if masters.is_empty() || stack.is_empty() {
+            (screen, screen)
+        } else {
+            self.split(screen)
+        };
+
+        let mut frames = self.tile(master_area, masters);
+        frames.extend(self.tile(stack_area, stack));
+        frames
+    }
+
+    /// Grows (positive `delta`) or shrinks the master area, keeping both
+    /// areas usable.
+    pub fn adjust_ratio(&mut self, delta: f64) {
+        self.ratio = clamp_ratio(self.ratio + delta);
+    }
+
+    pub fn adjust_master_count(&mut self, delta: isize) {
+        self.master_count = self.master_count.saturating_add_signed(delta);
+    }
+
+    /// Returns the (master, stack) areas.
+    fn split(&self, screen: Rect) -> (Rect, Rect) {
+        match self.stack_side {
+            StackSide::Right => screen.split_left_right(self.ratio),
+            StackSide::Bottom => screen.split_top_bottom(self.ratio),
+            StackSide::Left => {
+                let (stack, master) = screen.split_left_right(1.0 - self.ratio);
+                (master, stack)
+            }
+            StackSide::Top => {
+                let (stack, master) = screen.split_top_bottom(1.0 - self.ratio);
+                (master, stack)
+            }
+        }
+    }
+
+    /// Windows within an area run perpendicular to the master/stack split.
+    fn tile(&self, area: Rect, windows: &[WindowId]) -> Vec<(WindowId, Rect)> {
+        let cells = match self.stack_side {
+            StackSide::Left | StackSide::Right => area.rows(windows.len()),
+            StackSide::Top | StackSide::Bottom => area.columns(windows.len()),
+        };
+        windows.iter().copied().zip(cells).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::assert_tiles_cover;
+
+    const SCREEN: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1000.0,
+        height: 600.0,
+    };
+
+    fn windows(count: u32) -> Vec<WindowId> {
+        (0..count).map(WindowId).collect()
+    }
+
+    fn layout(stack_side: StackSide, master_count: usize) -> MasterStack {
+        MasterStack {
+            ratio: 0.6,
+            master_count,
+            stack_side,
+        }
+    }
+
+    #[test]
+    fn covers_screen_for_every_side_and_master_count() {
+        for side in [
+            StackSide::Left,
+            StackSide::Right,
+            StackSide::Top,
+            StackSide::Bottom,
+        ] {
+            for master_count in 0..5 {
+                for count in 0..8 {
+                    let windows = windows(count);
+                    let tiles = layout(side, master_count).arrange(SCREEN, &windows);
+                    assert_tiles_cover(SCREEN, &windows, &tiles);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stack_goes_on_the_configured_side() {
+        let windows = windows(3);
+        let arrange = |side| layout(side, 1).arrange(SCREEN, &windows);
+
+        assert_eq!(
+            arrange(StackSide::Right),
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 600.0, 600.0)),
+                (WindowId(1), Rect::new(600.0, 0.0, 400.0, 300.0)),
+                (WindowId(2), Rect::new(600.0, 300.0, 400.0, 300.0)),
+            ]
+        );
+        assert_eq!(
+            arrange(StackSide::Left),
+            vec![
+                (WindowId(0), Rect::new(400.0, 0.0, 600.0, 600.0)),
+                (WindowId(1), Rect::new(0.0, 0.0, 400.0, 300.0)),
+                (WindowId(2), Rect::new(0.0, 300.0, 400.0, 300.0)),
+            ]
+        );
+        assert_eq!(
+            arrange(StackSide::Bottom),
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 1000.0, 360.0)),
+                (WindowId(1), Rect::new(0.0, 360.0, 500.0, 240.0)),
+                (WindowId(2), Rect::new(500.0, 360.0, 500.0, 240.0)),
+            ]
+        );
+        assert_eq!(
+            arrange(StackSide::Top),
+            vec![
+                (WindowId(0), Rect::new(0.0, 240.0, 1000.0, 360.0)),
+                (WindowId(1), Rect::new(0.0, 0.0, 500.0, 240.0)),
+                (WindowId(2), Rect::new(500.0, 0.0, 500.0, 240.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn without_masters_the_stack_takes_the_screen() {
+        let tiles = layout(StackSide::Right, 0).arrange(SCREEN, &windows(2));
+
+        assert_eq!(
+            tiles,
+            vec![
+                (WindowId(0), Rect::new(0.0, 0.0, 1000.0, 300.0)),
+                (WindowId(1), Rect::new(0.0, 300.0, 1000.0, 300.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn more_masters_than_windows_share_the_screen() {
+        let tiles = layout(StackSide::Right, 5).arrange(SCREEN, &windows(2));
+
+        assert_eq!(
+            tiles,
+            layout(StackSide::Right, 0).arrange(SCREEN, &windows(2))
+        );
+    }
+
+    #[test]
+    fn ratio_and_master_count_stay_in_range() {
+        let mut layout = MasterStack::default();
+        layout.adjust_ratio(1.0);
+        assert_eq!(layout.ratio, 0.9);
+        layout.adjust_ratio(-2.0);
+        assert_eq!(layout.ratio, 0.1);
+
+        layout.adjust_master_count(-3);
+        assert_eq!(layout.master_count, 0);
+        layout.adjust_master_count(2);
+        assert_eq!(layout.master_count, 2);
+    }
+}
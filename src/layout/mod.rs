@@ -0,0 +1,146 @@
+//! Pure window layout computations.
+//!
+//! A layout maps a screen rectangle and an ordered list of windows to the
+//! frame each window should get. Nothing in this module talks to the window
+//! system, so every layout can be exercised on any platform.
+//!
+//! Layouts tile their area edge to edge; [`Gaps`] is applied on top so every
+//! layout gets the same spacing.
+
+mod bsp;
+mod dwindle;
+mod gaps;
+mod grid;
+mod master_stack;
+mod snap;
+
+pub use bsp::{BspTree, SplitAxis};
+pub use dwindle::{DwindleStyle, dwindle};
+pub use gaps::Gaps;
+pub use grid::{grid, grid_dimensions};
+pub use master_stack::{MasterStack, StackSide};
+pub use snap::{CYCLE, Snap};
+
+use crate::backend::WindowId;
+use crate::geometry::Rect;
+use serde::Deserialize;
+use std::ops::RangeInclusive;
+
+/// Which arrangement is applied when windows are auto-arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutKind {
+    /// Picks an arrangement from the window count, see [`auto`].
+    #[default]
+    Auto,
+    /// [`MasterStack`] at every window count, so the master ratio and count
+    /// always apply.
+    MasterStack,
+    /// A persistent [`BspTree`] per display.
+    Bsp,
+}
+
+/// The default arrangement: up to three windows use the master-stack layout
+/// (one window is maximized, two are split side by side, three get one master
+/// and two stacked), four windows form a 2x2 grid and anything more dwindles.
+pub fn auto(
+    screen: Rect,
+    windows: &[WindowId],
+    master_stack: &MasterStack,
+    dwindle_style: DwindleStyle,
+) -> Vec<(WindowId, Rect)> {
+    match windows.len() {
+        0..=3 => master_stack.arrange(screen, windows),
+        4 => grid(screen, windows),
+        _ => dwindle(screen, windows, dwindle_style),
+    }
+}
+
+/// Split ratios are kept in this range so neither side collapses.
+pub const RATIO_RANGE: RangeInclusive<f64> = 0.1..=0.9;
+
+fn clamp_ratio(ratio: f64) -> f64 {
+    ratio.clamp(*RATIO_RANGE.start(), *RATIO_RANGE.end())
+}
+
+/// Checks that `tiles` give each of `windows` one tile, in order, and cover
+/// `area` exactly once: inside it, without overlaps or holes.
+#[cfg(test)]
+fn assert_tiles_cover(area: Rect, windows: &[WindowId], tiles: &[(WindowId, Rect)]) {
+    const EPSILON: f64 = 1e-6;
+
+    let order: Vec<WindowId> = tiles.iter().map(|&(window, _)| window).collect();
+    assert_eq!(order, windows);
+
+    for (i, (_, a)) in tiles.iter().enumerate() {
+        assert!(
+            a.x >= area.x - EPSILON
+                && a.y >= area.y - EPSILON
+                && a.right() <= area.right() + EPSILON
+                && a.bottom() <= area.bottom() + EPSILON,
+            "{:?} is outside {:?}",
+            a,
+            area
+        );
+        for (_, b) in &tiles[i + 1..] {
+            let overlap_width = a.right().min(b.right()) - a.x.max(b.x);
+            let overlap_height = a.bottom().min(b.bottom()) - a.y.max(b.y);
+            assert!(
+                overlap_width <= EPSILON || overlap_height <= EPSILON,
+                "{:?} overlaps {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    // Tiles inside the area that do not overlap cover it when their areas
+    // add up to its area
+    if !tiles.is_empty() {
+        let covered: f64 = tiles.iter().map(|(_, t)| t.width * t.height).sum();
+        assert!(
+            (covered - area.width * area.height).abs() < EPSILON,
+            "tiles cover {} of {}",
+            covered,
+            area.width * area.height
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_covers_screen_for_any_window_count() {
+        let screen = Rect::new(0.0, 25.0, 1440.0, 875.0);
+        for count in 0..12 {
+            let windows: Vec<WindowId> = (0..count).map(WindowId).collect();
+            for style in [DwindleStyle::Dwindle, DwindleStyle::Spiral] {
+                let tiles = auto(screen, &windows, &MasterStack::default(), style);
+                assert_tiles_cover(screen, &windows, &tiles);
+            }
+        }
+    }
+
+    #[test]
+    fn auto_picks_layout_by_window_count() {
+        let screen = Rect::new(0.0, 0.0, 1000.0, 800.0);
+        let windows: Vec<WindowId> = (0..5).map(WindowId).collect();
+        let master_stack = MasterStack::default();
+        let style = DwindleStyle::Dwindle;
+
+        assert_eq!(
+            auto(screen, &windows[..3], &master_stack, style),
+            master_stack.arrange(screen, &windows[..3])
+        );
+        assert_eq!(
+            auto(screen, &windows[..4], &master_stack, style),
+            grid(screen, &windows[..4])
+        );
+        assert_eq!(
+            auto(screen, &windows, &master_stack, style),
+            dwindle(screen, &windows, style)
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use crate::geometry::Rect;
+
+/// A fixed spot on the screen a single window can be snapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Snap {
+    LeftHalf,
+    RightHalf,
+    TopHalf,
+    BottomHalf,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    LeftThird,
+    CenterThird,
+    RightThird,
+    LeftTwoThirds,
+    RightTwoThirds,
+    /// Two thirds of the width and height, in the middle.
+    Center,
+    Maximize,
+}
+
+/// Shares of the screen a half cycles through when snapped repeatedly.
+pub const CYCLE: [f64; 3] = [1.0 / 2.0, 2.0 / 3.0, 1.0 / 3.0];
+
+impl Snap {
+    /// Whether snapping again to the same spot moves on through [`CYCLE`].
+    /// Only the halves do.
+    pub fn cycles(self) -> bool {
+        matches!(
+            self,
+            Snap::LeftHalf | Snap::RightHalf | Snap::TopHalf | Snap::BottomHalf
+        )
+    }
+
+    /// The frame for the `step`th press in a row: halves take the share
+    /// `CYCLE[step % CYCLE.len()]` along the axis they split, everything else
+    /// ignores `step`.
+    pub fn cycle_frame(self, area: Rect, step: usize) -> Rect {
+        let share = CYCLE[step % CYCLE.len()];
+        // Left, top, width and height as fractions of the area
+        let (x, y, width, height) = match self {
+            Snap::LeftHalf => (0.0, 0.0, share, 1.0),
+            Snap::RightHalf => (1.0 - share, 0.0, share, 1.0),
+            Snap::TopHalf => (0.0, 0.0, 1.0, share),
+            Snap::BottomHalf => (0.0, 1.0 - share, 1.0, share),
+            Snap::TopLeft => (0.0, 0.0, 0.5, 0.5),
+            Snap::TopRight => (0.5, 0.0, 0.5, 0.5),
+            Snap::BottomLeft => (0.0, 0.5, 0.5, 0.5),
+            Snap::BottomRight => (0.5, 0.5, 0.5, 0.5),
+            Snap::LeftThird => (0.0, 0.0, 1.0 / 3.0, 1.0),
+            Snap::CenterThird => (1.0 / 3.0, 0.0, 1.0 / 3.0, 1.0),
+            Snap::RightThird => (2.0 / 3.0, 0.0, 1.0 / 3.0, 1.0),
+            Snap::LeftTwoThirds => (0.0, 0.0, 2.0 / 3.0, 1.0),
+            Snap::RightTwoThirds => (1.0 / 3.0, 0.0, 2.0 / 3.0, 1.0),
+            Snap::Center => (1.0 / 6.0, 1.0 / 6.0, 2.0 / 3.0, 2.0 / 3.0),
+            Snap::Maximize => (0.0, 0.0, 1.0, 1.0),
+        };
+        Rect::new(
+            area.x + area.width * x,
+            area.y + area.height * y,
+            area.width * width,
+            area.height * height,
+        )
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Snap::LeftHalf => "left half",
+            Snap::RightHalf => "right half",
+            Snap::TopHalf => "top half",
+            Snap::BottomHalf => "bottom half",
+            Snap::TopLeft => "top left quarter",
+            Snap::TopRight => "top right quarter",
+            Snap::BottomLeft => "bottom left quarter",
+            Snap::BottomRight => "bottom right quarter",
+            Snap::LeftThird => "left third",
+            Snap::CenterThird => "center third",
+            Snap::RightThird => "right third",
+            Snap::LeftTwoThirds => "left two thirds",
+            Snap::RightTwoThirds => "right two thirds",
+            Snap::Center => "center",
+            Snap::Maximize => "whole screen",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Away from the origin and divisible into sixths.
+    const AREA: Rect = Rect {
+        x: 100.0,
+        y: 50.0,
+        width: 1200.0,
+        height: 900.0,
+    };
+
+    fn assert_frame(snap: Snap, step: usize, expected: Rect) {
+        let frame = snap.cycle_frame(AREA, step);
+        assert!(
+            frame.approx_eq(&expected, 1e-9),
+            "{:?} step {}: {:?} != {:?}",
+            snap,
+            step,
+            frame,
+            expected
+        );
+    }
+
+    #[test]
+    fn every_spot_covers_its_part_of_the_area() {
+        let cases = [
+            (Snap::LeftHalf, Rect::new(100.0, 50.0, 600.0, 900.0)),
+            (Snap::RightHalf, Rect::new(700.0, 50.0, 600.0, 900.0)),
+            (Snap::TopHalf, Rect::new(100.0, 50.0, 1200.0, 450.0)),
+            (Snap::BottomHalf, Rect::new(100.0, 500.0, 1200.0, 450.0)),
+            (Snap::TopLeft, Rect::new(100.0, 50.0, 600.0, 450.0)),
+            (Snap::TopRight, Rect::new(700.0, 50.0, 600.0, 450.0)),
+            (Snap::BottomLeft, Rect::new(100.0, 500.0, 600.0, 450.0)),
+            (Snap::BottomRight, Rect::new(700.0, 500.0, 600.0, 450.0)),
+            (Snap::LeftThird, Rect::new(100.0, 50.0, 400.0, 900.0)),
+            (Snap::CenterThird, Rect::new(500.0, 50.0, 400.0, 900.0)),
+            (Snap::RightThird, Rect::new(900.0, 50.0, 400.0, 900.0)),
+            (Snap::LeftTwoThirds, Rect::new(100.0, 50.0, 800.0, 900.0)),
+            (Snap::RightTwoThirds, Rect::new(500.0, 50.0, 800.0, 900.0)),
+            (Snap::Center, Rect::new(300.0, 200.0, 800.0, 600.0)),
+            (Snap::Maximize, AREA),
+        ];
+        for (snap, expected) in cases {
+            assert_frame(snap, 0, expected);
+        }
+    }
+
+    #[test]
+    fn halves_cycle_through_shares() {
+        let cases = [
+            (
+                Snap::LeftHalf,
+                [
+                    Rect::new(100.0, 50.0, 600.0, 900.0),
+                    Rect::new(100.0, 50.0, 800.0, 900.0),
+                    Rect::new(100.0, 50.0, 400.0, 900.0),
+                ],
+            ),
+            (
+                Snap::RightHalf,
+                [
+                    Rect::new(700.0, 50.0, 600.0, 900.0),
+                    Rect::new(500.0, 50.0, 800.0, 900.0),
+                    Rect::new(900.0, 50.0, 400.0, 900.0),
+                ],
+            ),
+            (
+                Snap::TopHalf,
+                [
+                    Rect::new(100.0, 50.0, 1200.0, 450.0),
+                    Rect::new(100.0, 50.0, 1200.0, 600.0),
+                    Rect::new(100.0, 50.0, 1200.0, 300.0),
+                ],
+            ),
+            (
+                Snap::BottomHalf,
+                [
+                    Rect::new(100.0, 500.0, 1200.0, 450.0),
+                    Rect::new(100.0, 350.0, 1200.0, 600.0),
+                    Rect::new(100.0, 650.0, 1200.0, 300.0),
+                ],
+            ),
+        ];
+        for (snap, frames) in cases {
+            assert!(snap.cycles());
+            for (step, expected) in frames.into_iter().enumerate() {
+                assert_frame(snap, step, expected);
+                // And around again
+                assert_frame(snap, step + CYCLE.len(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn other_spots_ignore_the_step() {
+        for snap in [
+            Snap::TopLeft,
+            Snap::CenterThird,
+            Snap::Center,
+            Snap::Maximize,
+        ] {
+            assert!(!snap.cycles());
+            assert_frame(snap, 1, snap.cycle_frame(AREA, 0));
+            assert_frame(snap, 2, snap.cycle_frame(AREA, 0));
+        }
+    }
+}
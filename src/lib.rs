@@ -0,0 +1,10 @@
+pub mod access;
+pub mod actions;
+pub mod backend;
+pub mod config;
+pub mod doctor;
+pub mod error;
+pub mod geometry;
+pub mod history;
+pub mod hotkeys;
+pub mod layout;